@@ -5,6 +5,8 @@ Extract input cells from Mathematica notebooks.
 Usage:
     python extract-inputs.py --single <file.nb>          # Single file, JSON to stdout
     python extract-inputs.py --batch <directory>          # Batch mode, JSON to stdout
+    python extract-inputs.py --outputs <file.nb>         # Output cells, JSON to stdout
+    python extract-inputs.py --stream <file.nb>          # One JSON string per line
     python extract-inputs.py <input_dir> <output_dir>    # Directory mode (files to disk)
 
 Requirements:
@@ -95,6 +97,78 @@ def extract_inputs_from_notebook(session, nb_path):
         return []
 
 
+# Maps Mathematica cell styles to the output types reported to the app.
+OUTPUT_STYLES = {
+    "Output": "execute_result",
+    "Print": "stdout",
+    "Message": "stderr",
+    "MSG": "stderr",
+}
+
+
+def extract_outputs_from_notebook(session, nb_path):
+    """
+    Extract output cells from a single notebook.
+
+    Textual outputs are returned as InputForm strings. Outputs containing
+    graphics are rasterized to PNG and returned as base64 under the
+    "image/png" key of the MIME bundle instead.
+
+    Args:
+        session: Active WolframLanguageSession
+        nb_path: Path to the .nb file
+
+    Returns:
+        List of dicts with outputType, text, and mimeBundle keys
+    """
+    try:
+        escaped_path = str(nb_path).replace('\\', '\\\\').replace('"', '\\"')
+
+        wl_code = f'''
+        Module[{{nb, cells}},
+            nb = Import["{escaped_path}", "NB"];
+            If[nb === $Failed, Return[{{}}, Module]];
+
+            cells = Cases[nb,
+                Cell[content_, style : ("Output" | "Print" | "Message" | "MSG"), ___] :>
+                    {{style, content}},
+                Infinity];
+
+            (* Each entry becomes {{style, text, png}} with exactly one of text/png set *)
+            Apply[
+                Function[{{style, content}},
+                    Module[{{expr}},
+                        expr = ToExpression[content, StandardForm, HoldForm];
+                        If[FreeQ[expr, Graphics | Graphics3D | Image],
+                            {{style, ToString[expr, InputForm, PageWidth -> 80], Null}},
+                            {{style, Null, ExportString[ReleaseHold[expr], {{"Base64", "PNG"}}]}}
+                        ]
+                    ]
+                ],
+                cells,
+                {{1}}
+            ]
+        ]
+        '''
+
+        result = session.evaluate(wl_code)
+        if not result:
+            return []
+
+        outputs = []
+        for style, text, png in result:
+            outputs.append({
+                "outputType": OUTPUT_STYLES.get(str(style), "execute_result"),
+                "text": str(text) if text is not None else None,
+                "mimeBundle": {"image/png": str(png)} if png is not None else None
+            })
+        return outputs
+
+    except Exception as e:
+        print(f"Error processing {nb_path}: {e}", file=sys.stderr)
+        return []
+
+
 def save_inputs_to_file(inputs, output_path):
     """
     Save extracted inputs to a text file.
@@ -292,7 +366,67 @@ def process_single_file(nb_path):
         session.terminate()
 
 
+def process_stream_file(nb_path):
+    """
+    Process a single .nb file and print each extracted input as its own line
+    of JSON, flushing after every line so the app can consume cells as they
+    arrive.
+    """
+    path = Path(nb_path)
+    if not path.exists():
+        print(f"Error: File '{nb_path}' does not exist", file=sys.stderr)
+        sys.exit(1)
+    if not path.suffix == '.nb':
+        print(f"Error: '{nb_path}' is not a .nb file", file=sys.stderr)
+        sys.exit(1)
+
+    print("Starting Wolfram Language session...", file=sys.stderr)
+    try:
+        session = WolframLanguageSession()
+    except Exception as e:
+        print(f"Error: Could not start Wolfram Language session: {e}",
+              file=sys.stderr)
+        sys.exit(1)
+
+    try:
+        for inp in extract_inputs_from_notebook(session, path):
+            print(json.dumps(inp, ensure_ascii=False), flush=True)
+    finally:
+        session.terminate()
+
+
+def process_outputs_file(nb_path):
+    """
+    Process a single .nb file and print extracted outputs as JSON to stdout.
+    """
+    path = Path(nb_path)
+    if not path.exists():
+        print(f"Error: File '{nb_path}' does not exist", file=sys.stderr)
+        sys.exit(1)
+    if not path.suffix == '.nb':
+        print(f"Error: '{nb_path}' is not a .nb file", file=sys.stderr)
+        sys.exit(1)
+
+    print("Starting Wolfram Language session...", file=sys.stderr)
+    try:
+        session = WolframLanguageSession()
+    except Exception as e:
+        print(f"Error: Could not start Wolfram Language session: {e}",
+              file=sys.stderr)
+        sys.exit(1)
+
+    try:
+        outputs = extract_outputs_from_notebook(session, path)
+        print(json.dumps(outputs, ensure_ascii=False))
+    finally:
+        session.terminate()
+
+
 def main():
+    # The app parses stdout as UTF-8; without this, Windows consoles use the
+    # ANSI code page and non-ASCII cell content comes out as invalid bytes
+    sys.stdout.reconfigure(encoding='utf-8')
+
     parser = argparse.ArgumentParser(
         description="Extract input cells from Mathematica notebooks",
         formatter_class=argparse.RawDescriptionHelpFormatter,
@@ -300,6 +434,8 @@ def main():
 Examples:
   python extract-inputs.py --single notebook.nb
   python extract-inputs.py --batch ./submissions
+  python extract-inputs.py --outputs notebook.nb
+  python extract-inputs.py --stream notebook.nb
   python extract-inputs.py ./notebooks ./outputs
         """
     )
@@ -316,6 +452,18 @@ Examples:
         help='Extract inputs from all .nb files in directory (JSON to stdout)'
     )
 
+    parser.add_argument(
+        '--outputs',
+        metavar='FILE',
+        help='Extract output cells from a single .nb file (JSON to stdout)'
+    )
+
+    parser.add_argument(
+        '--stream',
+        metavar='FILE',
+        help='Extract inputs from a single .nb file (one JSON string per line)'
+    )
+
     parser.add_argument(
         'input_dir',
         nargs='?',
@@ -334,6 +482,10 @@ Examples:
         process_single_file(args.single)
     elif args.batch:
         process_batch_directory(args.batch)
+    elif args.outputs:
+        process_outputs_file(args.outputs)
+    elif args.stream:
+        process_stream_file(args.stream)
     elif args.input_dir and args.output_dir:
         process_directory(args.input_dir, args.output_dir)
     else:
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::cell::Cell;
+
+/// Extracted input cells keyed by path, remembered alongside the file's
+/// modified time when they were extracted. Keeping cells rather than bare
+/// sources keeps their hashes too.
+#[derive(Default)]
+pub struct InputCache(Mutex<HashMap<String, (SystemTime, Vec<Cell>)>>);
+
+impl InputCache {
+    /// Returns the cached cells if they were extracted from a file with this
+    /// exact mtime. A stale entry is evicted.
+    pub fn get(&self, path: &str, mtime: SystemTime) -> Option<Vec<Cell>> {
+        let mut entries = self.0.lock().unwrap();
+        match entries.get(path) {
+            Some((cached, cells)) if *cached == mtime => Some(cells.clone()),
+            Some(_) => {
+                entries.remove(path);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, path: &str, mtime: SystemTime, cells: Vec<Cell>) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), (mtime, cells));
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
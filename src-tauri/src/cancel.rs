@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use tokio_util::sync::CancellationToken;
+
+/// Cancellation tokens for in-flight `start_extraction` calls, keyed by the
+/// frontend's request id. Each entry carries a generation number so a
+/// finished call never removes a newer call that reused its id.
+#[derive(Default)]
+pub struct Extractions(Mutex<ExtractionsInner>);
+
+#[derive(Default)]
+struct ExtractionsInner {
+    next_generation: u64,
+    tokens: HashMap<String, (u64, CancellationToken)>,
+}
+
+impl Extractions {
+    /// Registers a new token for `request_id`, cancelling any extraction still
+    /// running under the same id. Returns the generation to pass to `finish`.
+    pub fn start(&self, request_id: &str) -> (u64, CancellationToken) {
+        let mut inner = self.0.lock().unwrap();
+        let generation = inner.next_generation;
+        inner.next_generation += 1;
+        let token = CancellationToken::new();
+        let previous = inner
+            .tokens
+            .insert(request_id.to_string(), (generation, token.clone()));
+        if let Some((_, previous)) = previous {
+            previous.cancel();
+        }
+        (generation, token)
+    }
+
+    pub fn finish(&self, request_id: &str, generation: u64) {
+        let mut inner = self.0.lock().unwrap();
+        if matches!(inner.tokens.get(request_id), Some((g, _)) if *g == generation) {
+            inner.tokens.remove(request_id);
+        }
+    }
+
+    pub fn cancel(&self, request_id: &str) {
+        if let Some((_, token)) = self.0.lock().unwrap().tokens.remove(request_id) {
+            token.cancel();
+        }
+    }
+}
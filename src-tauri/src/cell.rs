@@ -0,0 +1,94 @@
+use crate::diff::cell_hash;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CellType {
+    Code,
+    Markdown,
+    Raw,
+}
+
+/// A file embedded in a markdown cell's `attachments`, without its data.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct Attachment {
+    pub name: String,
+    pub mime: String,
+}
+
+/// A notebook cell with its type, for views that need more than the bare
+/// input text returned by `extract_inputs`.
+///
+/// `hash` is `diff::cell_hash` of the source, computed once when the cell is
+/// built so the input cache and diff keys can reuse it. It is not sent to the
+/// frontend, which cannot hold a `u64` exactly.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", from = "CellFields")]
+pub struct Cell {
+    pub cell_type: CellType,
+    pub source: String,
+    pub execution_count: Option<u64>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(skip_serializing)]
+    pub hash: u64,
+}
+
+/// A `Cell` as it arrives from the frontend, before its hash is computed.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CellFields {
+    cell_type: CellType,
+    source: String,
+    execution_count: Option<u64>,
+    #[serde(default)]
+    attachments: Vec<Attachment>,
+}
+
+impl From<CellFields> for Cell {
+    fn from(fields: CellFields) -> Self {
+        Cell::new(
+            fields.cell_type,
+            fields.source,
+            fields.execution_count,
+            fields.attachments,
+        )
+    }
+}
+
+impl Cell {
+    pub fn new(
+        cell_type: CellType,
+        source: String,
+        execution_count: Option<u64>,
+        attachments: Vec<Attachment>,
+    ) -> Self {
+        Cell {
+            hash: cell_hash(&source),
+            cell_type,
+            source,
+            execution_count,
+            attachments,
+        }
+    }
+
+    /// A code cell from the Mathematica extractor, which does not report
+    /// execution counts.
+    pub fn code(source: String) -> Self {
+        Cell::new(CellType::Code, source, None, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_is_computed_on_deserialize_and_not_serialized() {
+        let cell: Cell =
+            serde_json::from_str(r#"{"cellType": "code", "source": "x = 1"}"#).unwrap();
+        assert_eq!(cell, Cell::code("x = 1".to_string()));
+        assert_eq!(cell.hash, cell_hash("x = 1"));
+        let json = serde_json::to_value(&cell).unwrap();
+        assert!(json.get("hash").is_none());
+    }
+}
@@ -0,0 +1,429 @@
+use std::path::Path;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::cell::Cell;
+use crate::diff::{self, CellDiff, CellKey, DiffOptions};
+use crate::error::NbError;
+use crate::extractor::{self, Extractor};
+use crate::folders::{self, FileDiffEntry, FileStatus};
+use crate::ipynb;
+use crate::merge::{self, MergeResult};
+use crate::remote;
+
+/// Cell texts plus the keys they are compared by.
+pub struct DiffSide {
+    pub texts: Vec<String>,
+    pub keys: Vec<CellKey>,
+}
+
+pub fn notebook_diff_side(notebook: &Value, opts: &DiffOptions) -> Result<DiffSide, NbError> {
+    let cells = ipynb::cells(notebook)?;
+    let keys = cells
+        .iter()
+        .zip(ipynb::cell_values(notebook))
+        .map(|(cell, raw)| CellKey::from_cell(cell, Some(raw), opts))
+        .collect();
+    let texts = cells.into_iter().map(|cell| cell.source).collect();
+    Ok(DiffSide { texts, keys })
+}
+
+/// A diff side for cells that have no JSON of their own, keyed by their
+/// stored hashes.
+fn cells_diff_side(cells: Vec<Cell>, opts: &DiffOptions) -> DiffSide {
+    let keys = cells
+        .iter()
+        .map(|cell| CellKey::from_cell(cell, None, opts))
+        .collect();
+    let texts = cells.into_iter().map(|cell| cell.source).collect();
+    DiffSide { texts, keys }
+}
+
+/// Whether a local `path` is definitely absent. Other metadata errors (such
+/// as permissions) are left for the reader to report.
+fn is_missing(path: &str) -> bool {
+    !remote::is_remote(path)
+        && matches!(std::fs::metadata(path), Err(e) if e.kind() == std::io::ErrorKind::NotFound)
+}
+
+async fn read_diff_side(
+    extractor: &dyn Extractor,
+    path: &str,
+    opts: &DiffOptions,
+    timeout: Duration,
+) -> Result<DiffSide, NbError> {
+    if extractor::is_ipynb(path) {
+        let notebook = extractor::read_ipynb(extractor, path, timeout).await?;
+        return notebook_diff_side(&notebook, opts);
+    }
+
+    let cells = extractor::extract_cells(extractor, path, timeout).await?;
+    Ok(cells_diff_side(cells, opts))
+}
+
+/// Builds a diff side from file contents that are not on disk, such as a
+/// committed revision. `original` is the path they belong to and decides how
+/// they are read.
+pub async fn contents_diff_side(
+    extractor: &dyn Extractor,
+    contents: &[u8],
+    original: &str,
+    opts: &DiffOptions,
+    timeout: Duration,
+) -> Result<DiffSide, NbError> {
+    if extractor::is_ipynb(original) {
+        let notebook = ipynb::parse_notebook(contents)?;
+        return notebook_diff_side(&notebook, opts);
+    }
+
+    let cells = if extractor::is_percent_script(original) {
+        extractor::parse_percent_script(contents)?
+    } else {
+        let tmp = extractor::write_temp_notebook(contents, original)?;
+        let tmp = tmp.to_string_lossy();
+        let inputs = extractor.extract(&tmp, timeout, None).await?;
+        inputs.into_iter().map(Cell::code).collect()
+    };
+    Ok(cells_diff_side(cells, opts))
+}
+
+/// Loads one side of a diff. With `allow_missing`, a local file that does not
+/// exist is an empty notebook; any other failure, including a remote notebook
+/// that cannot be fetched, is still an error.
+pub async fn load_diff_side(
+    extractor: &dyn Extractor,
+    path: &str,
+    opts: &DiffOptions,
+    timeout: Duration,
+) -> Result<DiffSide, NbError> {
+    match read_diff_side(extractor, path, opts, timeout).await {
+        Err(_) if opts.allow_missing && is_missing(path) => Ok(DiffSide {
+            texts: Vec::new(),
+            keys: Vec::new(),
+        }),
+        side => side,
+    }
+}
+
+pub async fn compute_diff_with(
+    extractor: &dyn Extractor,
+    path_a: &str,
+    path_b: &str,
+    opts: &DiffOptions,
+    timeout: Duration,
+) -> Result<Vec<CellDiff>, NbError> {
+    // Jupyter notebooks are read without the extractor, so a missing one is
+    // known up front and left to `load_diff_side`
+    let both_ipynb = extractor::is_ipynb(path_a) && extractor::is_ipynb(path_b);
+    let both_present = !opts.allow_missing || !(is_missing(path_a) || is_missing(path_b));
+    let (a, b) = if opts.upconvert_format && both_present && both_ipynb {
+        let mut notebook_a = extractor::read_ipynb(extractor, path_a, timeout).await?;
+        let mut notebook_b = extractor::read_ipynb(extractor, path_b, timeout).await?;
+        let ids_added = ipynb::upconvert_to_match(&mut notebook_a, &mut notebook_b)?;
+        let mut a = notebook_diff_side(&notebook_a, opts)?;
+        let mut b = notebook_diff_side(&notebook_b, opts)?;
+        if ids_added {
+            a.keys
+                .iter_mut()
+                .chain(&mut b.keys)
+                .for_each(CellKey::forget_id);
+        }
+        (a, b)
+    } else {
+        (
+            load_diff_side(extractor, path_a, opts, timeout).await?,
+            load_diff_side(extractor, path_b, opts, timeout).await?,
+        )
+    };
+    Ok(diff::diff_cells_keyed(
+        &a.texts, &b.texts, &a.keys, &b.keys, opts,
+    ))
+}
+
+pub async fn merge_files(
+    extractor: &dyn Extractor,
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    timeout: Duration,
+) -> Result<MergeResult, NbError> {
+    let base_cells = extractor::extract_sources(extractor, base, timeout).await?;
+    let our_cells = extractor::extract_sources(extractor, ours, timeout).await?;
+    let their_cells = extractor::extract_sources(extractor, theirs, timeout).await?;
+    Ok(merge::merge_cells(&base_cells, &our_cells, &their_cells))
+}
+
+/// Compares the notebooks under two folders by relative path. Files present
+/// on both sides are `Modified` when their cell keys differ under `opts`, so
+/// what counts as a change matches `compute_diff_with`.
+pub async fn diff_folders(
+    extractor: &dyn Extractor,
+    dir_a: &str,
+    dir_b: &str,
+    opts: &DiffOptions,
+    timeout: Duration,
+) -> Result<Vec<FileDiffEntry>, NbError> {
+    let files_a = folders::notebooks_in(dir_a)?;
+    let files_b = folders::notebooks_in(dir_b)?;
+
+    let mut entries = Vec::new();
+    for rel_path in files_a.union(&files_b) {
+        let status = match (files_a.contains(rel_path), files_b.contains(rel_path)) {
+            (true, false) => FileStatus::Removed,
+            (false, true) => FileStatus::Added,
+            _ => {
+                let path_a = Path::new(dir_a).join(rel_path);
+                let path_b = Path::new(dir_b).join(rel_path);
+                let a = load_diff_side(extractor, &path_a.to_string_lossy(), opts, timeout).await?;
+                let b = load_diff_side(extractor, &path_b.to_string_lossy(), opts, timeout).await?;
+                if a.keys == b.keys {
+                    FileStatus::Unchanged
+                } else {
+                    FileStatus::Modified
+                }
+            }
+        };
+        entries.push(FileDiffEntry {
+            rel_path: rel_path.clone(),
+            status,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::diff::DiffStats;
+    use crate::extractor::MockExtractor;
+    use crate::merge::MergedCell;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    fn mock() -> MockExtractor {
+        MockExtractor::default()
+            .with("key.nb", &["x = 1", "y = 2", "Plot[x]"])
+            .with("student.nb", &["x = 1", "y = 3", "Plot[x]", "z = 4"])
+    }
+
+    #[test]
+    fn diff_reports_modified_and_added_cells() {
+        let opts = DiffOptions::default();
+        let diffs = block_on(compute_diff_with(
+            &mock(),
+            "key.nb",
+            "student.nb",
+            &opts,
+            TIMEOUT,
+        ))
+        .unwrap();
+        let stats = DiffStats::from_diffs(&diffs);
+        assert_eq!(stats.unchanged, 2);
+        assert_eq!(stats.modified, 1);
+        assert_eq!(stats.added, 1);
+        assert_eq!(stats.removed, 0);
+    }
+
+    #[test]
+    fn identical_notebooks_are_all_unchanged() {
+        let opts = DiffOptions::default();
+        let diffs = block_on(compute_diff_with(
+            &mock(),
+            "key.nb",
+            "key.nb",
+            &opts,
+            TIMEOUT,
+        ))
+        .unwrap();
+        assert!(diffs
+            .iter()
+            .all(|diff| matches!(diff, CellDiff::Unchanged { .. })));
+    }
+
+    #[test]
+    fn missing_side_is_empty_only_when_allowed() {
+        let mut opts = DiffOptions::default();
+        let missing = block_on(compute_diff_with(
+            &mock(),
+            "gone.nb",
+            "key.nb",
+            &opts,
+            TIMEOUT,
+        ));
+        assert!(matches!(missing, Err(NbError::FileNotFound { .. })));
+
+        opts.allow_missing = true;
+        let diffs = block_on(compute_diff_with(
+            &mock(),
+            "gone.nb",
+            "key.nb",
+            &opts,
+            TIMEOUT,
+        ))
+        .unwrap();
+        assert_eq!(DiffStats::from_diffs(&diffs).added, 3);
+
+        // A remote notebook that cannot be fetched is not "missing"
+        let unreachable = block_on(compute_diff_with(
+            &mock(),
+            "https://host/gone.ipynb",
+            "key.nb",
+            &opts,
+            TIMEOUT,
+        ));
+        assert!(matches!(unreachable, Err(NbError::Http { .. })));
+    }
+
+    #[test]
+    fn committed_notebook_is_read_from_its_bytes() {
+        let contents = br#"{"nbformat": 4, "nbformat_minor": 5, "metadata": {},
+            "cells": [{"cell_type": "code", "source": ["x = 1\n", "x"]}]}"#;
+        let opts = DiffOptions::default();
+        let side = block_on(contents_diff_side(
+            &mock(),
+            contents,
+            "analysis.ipynb",
+            &opts,
+            TIMEOUT,
+        ))
+        .unwrap();
+        assert_eq!(side.texts, ["x = 1\nx"]);
+        assert_eq!(side.keys.len(), 1);
+    }
+
+    /// Writes a v4.5 notebook of code cells to `dir/name`.
+    fn write_ipynb(dir: &Path, name: &str, sources: &[&str]) -> String {
+        let cells: Vec<Value> = sources
+            .iter()
+            .map(|source| serde_json::json!({"cell_type": "code", "source": source}))
+            .collect();
+        write_notebook_json(dir, name, 5, cells)
+    }
+
+    fn write_notebook_json(dir: &Path, name: &str, minor: u32, cells: Vec<Value>) -> String {
+        let notebook = serde_json::json!({
+            "nbformat": 4,
+            "nbformat_minor": minor,
+            "metadata": {},
+            "cells": cells,
+        });
+        let path = dir.join(name).to_string_lossy().into_owned();
+        ipynb::write_notebook(&path, &notebook).unwrap();
+        path
+    }
+
+    #[test]
+    fn upconverted_ids_are_not_compared() {
+        let dir = tempfile::tempdir().unwrap();
+        let cell = |id: Option<&str>, source: &str| {
+            let mut cell =
+                serde_json::json!({"cell_type": "code", "metadata": {}, "source": source});
+            if let Some(id) = id {
+                cell["id"] = Value::from(id);
+            }
+            cell
+        };
+        let old = write_notebook_json(
+            dir.path(),
+            "old.ipynb",
+            4,
+            vec![cell(None, "x = 1"), cell(None, "x")],
+        );
+        let new = write_notebook_json(
+            dir.path(),
+            "new.ipynb",
+            5,
+            vec![cell(Some("a1b2"), "x = 1"), cell(Some("c3d4"), "x")],
+        );
+        let opts = DiffOptions {
+            ignore_metadata: false,
+            upconvert_format: true,
+            ..DiffOptions::default()
+        };
+        let diffs = block_on(compute_diff_with(&mock(), &old, &new, &opts, TIMEOUT)).unwrap();
+        assert!(diffs
+            .iter()
+            .all(|diff| matches!(diff, CellDiff::Unchanged { .. })));
+
+        // Other metadata is still compared
+        let tagged = write_notebook_json(
+            dir.path(),
+            "tagged.ipynb",
+            5,
+            vec![
+                cell(Some("a1b2"), "x = 1"),
+                serde_json::json!({"cell_type": "code", "id": "c3d4",
+                    "metadata": {"tags": ["slow"]}, "source": "x"}),
+            ],
+        );
+        let diffs = block_on(compute_diff_with(&mock(), &old, &tagged, &opts, TIMEOUT)).unwrap();
+        assert_eq!(DiffStats::from_diffs(&diffs).unchanged, 1);
+    }
+
+    fn resolved_texts(result: &MergeResult) -> Vec<&str> {
+        result
+            .cells
+            .iter()
+            .map(|cell| match cell {
+                MergedCell::Resolved { text, .. } => text.as_str(),
+                MergedCell::Conflict { .. } => panic!("unexpected conflict"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn remote_notebooks_are_downloaded_and_read_directly() {
+        let url = "https://host/course/a.ipynb";
+        let body = br##"{"nbformat": 4, "nbformat_minor": 5, "metadata": {},
+            "cells": [{"cell_type": "code", "source": "import os"},
+                      {"cell_type": "markdown", "source": ["# Notes"]}]}"##;
+        let extractor = mock().with_download(url, body);
+        let dir = tempfile::tempdir().unwrap();
+        let local = write_ipynb(dir.path(), "a.ipynb", &["import os", "x = 1"]);
+
+        let cells = block_on(extractor::extract_cells(&extractor, url, TIMEOUT)).unwrap();
+        assert_eq!(cells[1].source, "# Notes");
+
+        let opts = DiffOptions::default();
+        let diffs = block_on(compute_diff_with(&extractor, url, &local, &opts, TIMEOUT)).unwrap();
+        let stats = DiffStats::from_diffs(&diffs);
+        assert_eq!((stats.unchanged, stats.modified), (1, 1));
+    }
+
+    #[test]
+    fn merge_takes_one_sided_changes() {
+        let extractor = mock().with(
+            "theirs.nb",
+            &["Needs[\"Pkg`\"]", "x = 1", "y = 2", "Plot[x]"],
+        );
+        let result = block_on(merge_files(
+            &extractor,
+            "key.nb",
+            "student.nb",
+            "theirs.nb",
+            TIMEOUT,
+        ))
+        .unwrap();
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            resolved_texts(&result),
+            ["Needs[\"Pkg`\"]", "x = 1", "y = 3", "Plot[x]", "z = 4"]
+        );
+
+        // Jupyter notebooks are read directly, never handed to the extractor
+        let dir = tempfile::tempdir().unwrap();
+        let base = write_ipynb(dir.path(), "base.ipynb", &["import os", "y = 0", "x = 1"]);
+        let ours = write_ipynb(dir.path(), "ours.ipynb", &["import os", "y = 0", "x = 2"]);
+        let theirs = write_ipynb(
+            dir.path(),
+            "theirs.ipynb",
+            &["import sys", "y = 0", "x = 1"],
+        );
+        let result = block_on(merge_files(&mock(), &base, &ours, &theirs, TIMEOUT)).unwrap();
+        assert!(result.conflicts.is_empty());
+        assert_eq!(resolved_texts(&result), ["import sys", "y = 0", "x = 2"]);
+    }
+}
@@ -0,0 +1,672 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::cell::{Attachment, Cell, CellType};
+use crate::ipynb;
+
+/// Controls what counts as a change when diffing. The defaults compare cell
+/// sources only, exactly as `diff_notebooks` always has.
+///
+/// Execution counts, metadata (including nbformat 4.5 cell `id`s) and outputs
+/// only exist for Jupyter notebooks read directly; for extractor-backed
+/// formats those flags have no effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DiffOptions {
+    pub ignore_execution_count: bool,
+    pub ignore_metadata: bool,
+    pub ignore_outputs: bool,
+    /// Unify line endings and drop trailing whitespace before comparing.
+    pub normalize_whitespace: bool,
+    /// When two Jupyter notebooks have different minor format versions,
+    /// upconvert the older one first (see `ipynb::upconvert_to_match`). Cell
+    /// ids are then left out of the comparison if the upconvert made them up.
+    pub upconvert_format: bool,
+    pub granularity: DiffGranularity,
+    /// Report cells that were removed in one place and added unchanged in
+    /// another as `Moved` rather than as a removal plus an addition.
+    pub match_by_content: bool,
+    /// Treat a path that does not exist as an empty notebook, so every cell
+    /// on the other side comes back `Added` (or `Removed`).
+    pub allow_missing: bool,
+}
+
+/// How finely modified cells are broken down. Line changes are always
+/// reported; `Word` adds word changes on top.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffGranularity {
+    #[default]
+    Line,
+    Word,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            ignore_execution_count: true,
+            ignore_metadata: true,
+            ignore_outputs: true,
+            normalize_whitespace: false,
+            upconvert_format: false,
+            granularity: DiffGranularity::Line,
+            match_by_content: false,
+            allow_missing: false,
+        }
+    }
+}
+
+/// What two cells are compared by, built according to `DiffOptions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellKey {
+    // Compared first by the derived `PartialEq`, so cells with different
+    // sources are usually told apart without comparing the strings
+    hash: u64,
+    source: String,
+    execution_count: Option<Value>,
+    metadata: Option<(Option<Value>, Option<Value>)>,
+    outputs: Option<Value>,
+    /// Always compared: attachments are content, not metadata.
+    attachments: Vec<Attachment>,
+}
+
+impl CellKey {
+    /// `raw` is the cell's JSON for Jupyter notebooks, or `None` when only the
+    /// source is known.
+    pub fn new(source: &str, raw: Option<&Value>, opts: &DiffOptions) -> Self {
+        let source = if opts.normalize_whitespace {
+            normalize_whitespace(source)
+        } else {
+            source.to_string()
+        };
+        CellKey::with_hash(cell_hash(&source), source, raw, opts)
+    }
+
+    /// Like `new`, but reuses the hash stored on `cell` when the source is
+    /// compared as-is.
+    pub fn from_cell(cell: &Cell, raw: Option<&Value>, opts: &DiffOptions) -> Self {
+        if opts.normalize_whitespace {
+            return CellKey::new(&cell.source, raw, opts);
+        }
+        CellKey::with_hash(cell.hash, cell.source.clone(), raw, opts)
+    }
+
+    fn with_hash(hash: u64, source: String, raw: Option<&Value>, opts: &DiffOptions) -> Self {
+        let field = |name: &str| raw.and_then(|cell| cell.get(name)).cloned();
+        CellKey {
+            hash,
+            source,
+            execution_count: (!opts.ignore_execution_count)
+                .then(|| field("execution_count"))
+                .flatten(),
+            metadata: (!opts.ignore_metadata).then(|| (field("id"), field("metadata"))),
+            outputs: (!opts.ignore_outputs).then(|| field("outputs")).flatten(),
+            attachments: raw.map(ipynb::attachments).unwrap_or_default(),
+        }
+    }
+
+    /// Stops comparing the cell's `id`, keeping the rest of its metadata.
+    pub fn forget_id(&mut self) {
+        if let Some((id, _)) = &mut self.metadata {
+            *id = None;
+        }
+    }
+
+    /// Whether the two keys differ in their attachments and nothing else.
+    fn only_attachments_differ(&self, other: &CellKey) -> bool {
+        self.attachments != other.attachments
+            && self.hash == other.hash
+            && self.source == other.source
+            && self.execution_count == other.execution_count
+            && self.metadata == other.metadata
+            && self.outputs == other.outputs
+    }
+}
+
+/// A hash of a cell's source that is stable across runs and platforms, so it
+/// can be stored and compared later. Callers wanting whitespace-insensitive
+/// hashes should pass the output of `normalize_whitespace`.
+pub fn cell_hash(source: &str) -> u64 {
+    seahash::hash(source.as_bytes())
+}
+
+/// A SHA-256 hex digest of a whole notebook's cell types, sources (after
+/// `normalize_whitespace` if the options ask for it) and attachment names and
+/// types. Execution counts are included only when `ignore_execution_count` is
+/// off; outputs and metadata never are. Each field is length-prefixed so cell
+/// boundaries cannot shift.
+pub fn content_hash(cells: &[Cell], opts: &DiffOptions) -> String {
+    let mut hasher = Sha256::new();
+    let mut field = |bytes: &[u8]| {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    };
+    for cell in cells {
+        let cell_type = match cell.cell_type {
+            CellType::Code => "code",
+            CellType::Markdown => "markdown",
+            CellType::Raw => "raw",
+        };
+        field(cell_type.as_bytes());
+        if opts.normalize_whitespace {
+            field(normalize_whitespace(&cell.source).as_bytes());
+        } else {
+            field(cell.source.as_bytes());
+        }
+        if !opts.ignore_execution_count {
+            field(
+                &cell
+                    .execution_count
+                    .map_or(Vec::new(), |n| n.to_le_bytes().to_vec()),
+            );
+        }
+        let mut attachments: Vec<&Attachment> = cell.attachments.iter().collect();
+        attachments.sort();
+        field(&(attachments.len() as u64).to_le_bytes());
+        for attachment in attachments {
+            field(attachment.name.as_bytes());
+            field(attachment.mime.as_bytes());
+        }
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Converts `\r\n` and lone `\r` to `\n`, strips trailing whitespace from
+/// every line, and drops trailing blank lines.
+pub fn normalize_whitespace(text: &str) -> String {
+    let unified = text.replace("\r\n", "\n").replace('\r', "\n");
+    let lines: Vec<&str> = unified.lines().map(str::trim_end).collect();
+    lines.join("\n").trim_end().to_string()
+}
+
+/// A single cell-level difference between two notebooks.
+///
+/// `Unchanged` and `Removed` indices refer to notebook A, `Added` indices
+/// refer to notebook B. `Moved` goes from an index in A to one in B.
+/// `AttachmentChanged` is a cell whose text is identical on both sides but
+/// whose attachments were added, removed or changed type.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum CellDiff {
+    Unchanged {
+        index: usize,
+        text: String,
+    },
+    Added {
+        index: usize,
+        text: String,
+    },
+    Removed {
+        index: usize,
+        text: String,
+    },
+    Modified {
+        index_a: usize,
+        index_b: usize,
+        text_a: String,
+        text_b: String,
+        line_changes: Vec<LineChange>,
+        /// Only present with `DiffGranularity::Word`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        word_changes: Option<Vec<WordChange>>,
+    },
+    Moved {
+        from: usize,
+        to: usize,
+        text: String,
+    },
+    AttachmentChanged {
+        index_a: usize,
+        index_b: usize,
+        text: String,
+        attachments_a: Vec<Attachment>,
+        attachments_b: Vec<Attachment>,
+    },
+}
+
+/// A line within a modified cell.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum LineChange {
+    Unchanged { text: String },
+    Added { text: String },
+    Removed { text: String },
+}
+
+/// A run of tokens within a modified cell. Concatenating the `Unchanged` and
+/// `Removed` texts gives cell A; `Unchanged` and `Added` gives cell B.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum WordChange {
+    Unchanged { text: String },
+    Added { text: String },
+    Removed { text: String },
+}
+
+/// Per-kind cell counts for a computed diff.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffStats {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub unchanged: usize,
+    pub moved: usize,
+    pub attachment_changed: usize,
+}
+
+impl DiffStats {
+    pub fn from_diffs(diffs: &[CellDiff]) -> Self {
+        let mut stats = DiffStats::default();
+        for diff in diffs {
+            match diff {
+                CellDiff::Unchanged { .. } => stats.unchanged += 1,
+                CellDiff::Added { .. } => stats.added += 1,
+                CellDiff::Removed { .. } => stats.removed += 1,
+                CellDiff::Modified { .. } => stats.modified += 1,
+                CellDiff::Moved { .. } => stats.moved += 1,
+                CellDiff::AttachmentChanged { .. } => stats.attachment_changed += 1,
+            }
+        }
+        stats
+    }
+}
+
+/// One step of an edit script. Indices point into the original sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Computes the shortest edit script turning `a` into `b` using Myers'
+/// O(ND) algorithm. The trace keeps only the diagonals `-d..=d` reached at
+/// each step, so it takes O(D^2) memory rather than O(D * (N + M)).
+pub(crate) fn myers<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Edit> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+
+    let mut v = vec![0isize; 2 * max + 3];
+    // trace[d][k + d] is the furthest x reached on diagonal k after step d
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    for d in 0..=max as isize {
+        let mut done = false;
+        let mut k = -d;
+        while k <= d {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                done = true;
+                break;
+            }
+            k += 2;
+        }
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        if done {
+            break;
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len() as isize).rev() {
+        let prev = &trace[(d - 1) as usize];
+        let at = |k: isize| prev[(k + d - 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Equal((x - 1) as usize, (y - 1) as usize));
+            x -= 1;
+            y -= 1;
+        }
+        if x == prev_x {
+            edits.push(Edit::Insert((y - 1) as usize));
+        } else {
+            edits.push(Edit::Delete((x - 1) as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    // Whatever is left is the common prefix matched at step 0
+    while x > 0 && y > 0 {
+        edits.push(Edit::Equal((x - 1) as usize, (y - 1) as usize));
+        x -= 1;
+        y -= 1;
+    }
+
+    edits.reverse();
+    edits
+}
+
+/// Pairs each inserted cell with the first not-yet-paired deleted cell that
+/// has an equal key, returning `(i, j)` pairs in insertion order.
+fn find_moves(edits: &[Edit], keys_a: &[CellKey], keys_b: &[CellKey]) -> Vec<(usize, usize)> {
+    let mut deleted: Vec<Option<usize>> = edits
+        .iter()
+        .filter_map(|edit| match edit {
+            Edit::Delete(i) => Some(Some(*i)),
+            _ => None,
+        })
+        .collect();
+    let mut moves = Vec::new();
+    for edit in edits {
+        let Edit::Insert(j) = *edit else {
+            continue;
+        };
+        let found = deleted
+            .iter_mut()
+            .find(|i| matches!(i, Some(i) if keys_a[*i] == keys_b[j]));
+        if let Some(slot) = found {
+            moves.push((slot.take().unwrap(), j));
+        }
+    }
+    moves
+}
+
+/// Diffs two lists of cell sources, deciding equality by comparing
+/// `keys_a[i]` with `keys_b[j]` instead of the texts themselves. Within each
+/// run of changes, removed and added cells are paired up in order and
+/// reported as `Modified`; any leftovers are reported as plain `Removed` or
+/// `Added`. `opts` only controls the shape of the result (granularity and move
+/// detection); keys should already reflect the comparison options.
+pub fn diff_cells_keyed(
+    a: &[String],
+    b: &[String],
+    keys_a: &[CellKey],
+    keys_b: &[CellKey],
+    opts: &DiffOptions,
+) -> Vec<CellDiff> {
+    let edits = myers(keys_a, keys_b);
+    let moves = if opts.match_by_content {
+        find_moves(&edits, keys_a, keys_b)
+    } else {
+        Vec::new()
+    };
+    let sides = Sides {
+        a,
+        b,
+        keys_a,
+        keys_b,
+        granularity: opts.granularity,
+    };
+    let mut diffs = Vec::with_capacity(edits.len());
+    let mut removed: Vec<usize> = Vec::new();
+    let mut added: Vec<usize> = Vec::new();
+
+    for edit in edits {
+        match edit {
+            Edit::Delete(i) if moves.iter().any(|&(from, _)| from == i) => {}
+            // Pending changes are left in place so cells on either side of
+            // the move can still pair up as `Modified`
+            Edit::Insert(j) if moves.iter().any(|&(_, to)| to == j) => {
+                let (from, to) = *moves.iter().find(|&&(_, to)| to == j).unwrap();
+                diffs.push(CellDiff::Moved {
+                    from,
+                    to,
+                    text: b[j].clone(),
+                });
+            }
+            Edit::Equal(i, _) => {
+                flush_changes(&sides, &mut removed, &mut added, &mut diffs);
+                diffs.push(CellDiff::Unchanged {
+                    index: i,
+                    text: a[i].clone(),
+                });
+            }
+            Edit::Delete(i) => removed.push(i),
+            Edit::Insert(j) => added.push(j),
+        }
+    }
+    flush_changes(&sides, &mut removed, &mut added, &mut diffs);
+
+    diffs
+}
+
+/// The two notebooks being diffed, bundled for `flush_changes`.
+struct Sides<'a> {
+    a: &'a [String],
+    b: &'a [String],
+    keys_a: &'a [CellKey],
+    keys_b: &'a [CellKey],
+    granularity: DiffGranularity,
+}
+
+fn flush_changes(
+    sides: &Sides,
+    removed: &mut Vec<usize>,
+    added: &mut Vec<usize>,
+    diffs: &mut Vec<CellDiff>,
+) {
+    let Sides { a, b, .. } = *sides;
+    let paired = removed.len().min(added.len());
+    for (&i, &j) in removed.iter().zip(added.iter()) {
+        let (key_a, key_b) = (&sides.keys_a[i], &sides.keys_b[j]);
+        if key_a.only_attachments_differ(key_b) {
+            diffs.push(CellDiff::AttachmentChanged {
+                index_a: i,
+                index_b: j,
+                text: b[j].clone(),
+                attachments_a: key_a.attachments.clone(),
+                attachments_b: key_b.attachments.clone(),
+            });
+            continue;
+        }
+        diffs.push(CellDiff::Modified {
+            index_a: i,
+            index_b: j,
+            text_a: a[i].clone(),
+            text_b: b[j].clone(),
+            line_changes: diff_cell_lines(&a[i], &b[j]),
+            word_changes: (sides.granularity == DiffGranularity::Word)
+                .then(|| diff_cell_words(&a[i], &b[j])),
+        });
+    }
+    for &i in &removed[paired..] {
+        diffs.push(CellDiff::Removed {
+            index: i,
+            text: a[i].clone(),
+        });
+    }
+    for &j in &added[paired..] {
+        diffs.push(CellDiff::Added {
+            index: j,
+            text: b[j].clone(),
+        });
+    }
+    removed.clear();
+    added.clear();
+}
+
+/// Line-level diff of two cell sources. Lines are compared ignoring trailing
+/// whitespace, and a missing or extra final newline is not a change, so
+/// cells that differ only in those respects come back as all `Unchanged`
+/// (carrying B's text).
+pub fn diff_cell_lines(a: &str, b: &str) -> Vec<LineChange> {
+    let a_lines: Vec<&str> = a.lines().collect();
+    let b_lines: Vec<&str> = b.lines().collect();
+    let a_keys: Vec<&str> = a_lines.iter().map(|line| line.trim_end()).collect();
+    let b_keys: Vec<&str> = b_lines.iter().map(|line| line.trim_end()).collect();
+
+    myers(&a_keys, &b_keys)
+        .into_iter()
+        .map(|edit| match edit {
+            Edit::Equal(_, j) => LineChange::Unchanged {
+                text: b_lines[j].to_string(),
+            },
+            Edit::Delete(i) => LineChange::Removed {
+                text: a_lines[i].to_string(),
+            },
+            Edit::Insert(j) => LineChange::Added {
+                text: b_lines[j].to_string(),
+            },
+        })
+        .collect()
+}
+
+/// Splits code into identifiers, numbers, whitespace runs and single
+/// punctuation characters. Numbers keep `.`, `_` and letters so `1.5e3` and
+/// `0x1F` stay whole. Concatenating the tokens gives back `text`.
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(first) = rest.chars().next() {
+        let continues: fn(char) -> bool = if first.is_whitespace() {
+            char::is_whitespace
+        } else if first.is_ascii_digit() {
+            |c| c.is_alphanumeric() || c == '_' || c == '.'
+        } else if first.is_alphanumeric() || first == '_' {
+            |c| c.is_alphanumeric() || c == '_'
+        } else {
+            |_| false
+        };
+        let len = rest[first.len_utf8()..]
+            .find(|c: char| !continues(c))
+            .map_or(rest.len(), |end| end + first.len_utf8());
+        tokens.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+/// Token-level diff of two cell sources using `tokenize`. Consecutive tokens
+/// with the same kind of change are merged into one `WordChange`.
+pub fn diff_cell_words(a: &str, b: &str) -> Vec<WordChange> {
+    let a_tokens = tokenize(a);
+    let b_tokens = tokenize(b);
+
+    let mut changes: Vec<WordChange> = Vec::new();
+    for edit in myers(&a_tokens, &b_tokens) {
+        let change = match edit {
+            Edit::Equal(i, _) => WordChange::Unchanged {
+                text: a_tokens[i].to_string(),
+            },
+            Edit::Delete(i) => WordChange::Removed {
+                text: a_tokens[i].to_string(),
+            },
+            Edit::Insert(j) => WordChange::Added {
+                text: b_tokens[j].to_string(),
+            },
+        };
+        match (changes.last_mut(), change) {
+            (Some(WordChange::Unchanged { text }), WordChange::Unchanged { text: next })
+            | (Some(WordChange::Added { text }), WordChange::Added { text: next })
+            | (Some(WordChange::Removed { text }), WordChange::Removed { text: next }) => {
+                text.push_str(&next)
+            }
+            (_, change) => changes.push(change),
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that `edits` walks both sequences in order, keeps only equal
+    /// pairs, and returns the number of inserts and deletes.
+    fn replay(a: &[char], b: &[char], edits: &[Edit]) -> usize {
+        let (mut i, mut j, mut changes) = (0, 0, 0);
+        for edit in edits {
+            match *edit {
+                Edit::Equal(x, y) => {
+                    assert_eq!((x, y), (i, j));
+                    assert_eq!(a[x], b[y]);
+                    i += 1;
+                    j += 1;
+                }
+                Edit::Delete(x) => {
+                    assert_eq!(x, i);
+                    i += 1;
+                    changes += 1;
+                }
+                Edit::Insert(y) => {
+                    assert_eq!(y, j);
+                    j += 1;
+                    changes += 1;
+                }
+            }
+        }
+        assert_eq!((i, j), (a.len(), b.len()));
+        changes
+    }
+
+    #[test]
+    fn content_hash_covers_attachments_in_any_order() {
+        let opts = DiffOptions::default();
+        let attachment = |name: &str, mime: &str| Attachment {
+            name: name.to_string(),
+            mime: mime.to_string(),
+        };
+        let with = |attachments: Vec<Attachment>| {
+            let cell = Cell::new(
+                CellType::Markdown,
+                "![plot](attachment:a.png)".to_string(),
+                None,
+                attachments,
+            );
+            content_hash(&[cell], &opts)
+        };
+        let a = attachment("a.png", "image/png");
+        let b = attachment("b.jpg", "image/jpeg");
+        assert_ne!(with(Vec::new()), with(vec![a.clone()]));
+        assert_ne!(
+            with(vec![a.clone()]),
+            with(vec![attachment("a.png", "image/gif")])
+        );
+        assert_eq!(with(vec![a.clone(), b.clone()]), with(vec![b, a]));
+    }
+
+    #[test]
+    fn myers_finds_shortest_edit_scripts() {
+        let cases = [
+            ("", "", 0),
+            ("abc", "", 3),
+            ("", "abc", 3),
+            ("abc", "abc", 0),
+            ("abcabba", "cbabac", 5),
+            ("xabcx", "abc", 2),
+            ("abcd", "acbd", 2),
+        ];
+        for (a, b, distance) in cases {
+            let a: Vec<char> = a.chars().collect();
+            let b: Vec<char> = b.chars().collect();
+            assert_eq!(
+                replay(&a, &b, &myers(&a, &b)),
+                distance,
+                "{:?} -> {:?}",
+                a,
+                b
+            );
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use std::fmt;
+
+use serde::ser::SerializeMap;
+
+/// Errors surfaced to the frontend. Serializes as an object with a stable
+/// `kind` tag, a human-readable `message`, and any variant-specific fields.
+#[derive(Debug)]
+pub enum NbError {
+    SidecarSpawn {
+        detail: String,
+        attempts: u32,
+    },
+    /// `error` and `cell` come from a JSON diagnostic on stderr, when the
+    /// extractor printed one.
+    SidecarFailed {
+        code: Option<i32>,
+        stdout: String,
+        stderr: String,
+        error: Option<String>,
+        cell: Option<usize>,
+    },
+    FileNotFound {
+        path: String,
+    },
+    Parse {
+        detail: String,
+    },
+    Timeout {
+        timeout_ms: u64,
+    },
+    Io {
+        path: String,
+        detail: String,
+    },
+    Git {
+        detail: String,
+    },
+    Cancelled,
+    Http {
+        url: String,
+        status: Option<u16>,
+        detail: String,
+    },
+    Nbconvert {
+        detail: String,
+        stderr: Option<String>,
+    },
+    /// The extractor wrote bytes that are not valid UTF-8, starting at `offset`.
+    Encoding {
+        offset: usize,
+    },
+    /// A cell index past the end of a notebook with `count` cells.
+    CellOutOfRange {
+        index: usize,
+        count: usize,
+    },
+}
+
+/// The structured diagnostic the extractor may print as the last line of
+/// stderr, e.g. `{"error": "Syntax error", "cell": 3}`.
+#[derive(serde::Deserialize)]
+struct Diagnostic {
+    error: String,
+    cell: Option<usize>,
+}
+
+impl NbError {
+    /// Builds a `SidecarFailed` from raw process output, picking up a JSON
+    /// diagnostic from stderr if there is one. Any other stderr is kept as
+    /// plain text only.
+    pub fn sidecar_failed(code: Option<i32>, stdout: String, stderr: String) -> Self {
+        let diagnostic = stderr
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .filter(|line| line.starts_with('{'))
+            .and_then(|line| serde_json::from_str::<Diagnostic>(line).ok());
+        let (error, cell) = match diagnostic {
+            Some(d) => (Some(d.error), d.cell),
+            None => (None, None),
+        };
+        NbError::SidecarFailed {
+            code,
+            stdout,
+            stderr,
+            error,
+            cell,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NbError::SidecarSpawn { .. } => "sidecarSpawn",
+            NbError::SidecarFailed { .. } => "sidecarFailed",
+            NbError::FileNotFound { .. } => "fileNotFound",
+            NbError::Parse { .. } => "parse",
+            NbError::Timeout { .. } => "timeout",
+            NbError::Io { .. } => "io",
+            NbError::Git { .. } => "git",
+            NbError::Cancelled => "cancelled",
+            NbError::Http { .. } => "http",
+            NbError::Nbconvert { .. } => "nbconvert",
+            NbError::Encoding { .. } => "encoding",
+            NbError::CellOutOfRange { .. } => "cellOutOfRange",
+        }
+    }
+}
+
+impl fmt::Display for NbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NbError::SidecarSpawn { detail, attempts } => write!(
+                f,
+                "Failed to run sidecar after {} attempt(s): {}",
+                attempts, detail
+            ),
+            NbError::SidecarFailed {
+                code,
+                stderr,
+                error,
+                cell,
+                ..
+            } => {
+                write!(f, "extract-inputs failed")?;
+                if let Some(code) = code {
+                    write!(f, " (exit code {})", code)?;
+                }
+                match (error, cell) {
+                    (Some(error), Some(cell)) => write!(f, " in cell {}: {}", cell + 1, error),
+                    (Some(error), None) => write!(f, ": {}", error),
+                    (None, _) => write!(f, ": {}", stderr),
+                }
+            }
+            NbError::FileNotFound { path } => write!(f, "File not found: {}", path),
+            NbError::Parse { detail } => write!(f, "Failed to parse JSON: {}", detail),
+            NbError::Timeout { timeout_ms } => {
+                write!(f, "Extraction timed out after {} ms", timeout_ms)
+            }
+            NbError::Io { path, detail } => write!(f, "Failed to access {}: {}", path, detail),
+            NbError::Git { detail } => write!(f, "git: {}", detail),
+            NbError::Cancelled => write!(f, "Extraction was cancelled"),
+            NbError::Http { url, detail, .. } => write!(f, "Failed to fetch {}: {}", url, detail),
+            NbError::Nbconvert {
+                detail,
+                stderr: Some(stderr),
+            } if !stderr.is_empty() => write!(f, "nbconvert: {}: {}", detail, stderr),
+            NbError::Nbconvert { detail, .. } => write!(f, "nbconvert: {}", detail),
+            NbError::Encoding { offset } => write!(
+                f,
+                "extract-inputs output is not valid UTF-8 (first invalid byte at offset {})",
+                offset
+            ),
+            NbError::CellOutOfRange { index, count } => write!(
+                f,
+                "Cell index {} is out of range for a notebook with {} cell(s)",
+                index, count
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NbError {}
+
+impl serde::Serialize for NbError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", self.kind())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            NbError::SidecarSpawn { detail, attempts } => {
+                map.serialize_entry("detail", detail)?;
+                map.serialize_entry("attempts", attempts)?;
+            }
+            NbError::Parse { detail } | NbError::Git { detail } => {
+                map.serialize_entry("detail", detail)?;
+            }
+            NbError::SidecarFailed {
+                code,
+                stdout,
+                stderr,
+                error,
+                cell,
+            } => {
+                map.serialize_entry("code", code)?;
+                map.serialize_entry("stdout", stdout)?;
+                map.serialize_entry("stderr", stderr)?;
+                map.serialize_entry("error", error)?;
+                map.serialize_entry("cell", cell)?;
+            }
+            NbError::FileNotFound { path } => {
+                map.serialize_entry("path", path)?;
+            }
+            NbError::Timeout { timeout_ms } => {
+                map.serialize_entry("timeoutMs", timeout_ms)?;
+            }
+            NbError::Io { path, detail } => {
+                map.serialize_entry("path", path)?;
+                map.serialize_entry("detail", detail)?;
+            }
+            NbError::Cancelled => {}
+            NbError::Http {
+                url,
+                status,
+                detail,
+            } => {
+                map.serialize_entry("url", url)?;
+                map.serialize_entry("status", status)?;
+                map.serialize_entry("detail", detail)?;
+            }
+            NbError::Nbconvert { detail, stderr } => {
+                map.serialize_entry("detail", detail)?;
+                map.serialize_entry("stderr", stderr)?;
+            }
+            NbError::Encoding { offset } => {
+                map.serialize_entry("offset", offset)?;
+            }
+            NbError::CellOutOfRange { index, count } => {
+                map.serialize_entry("index", index)?;
+                map.serialize_entry("count", count)?;
+            }
+        }
+        map.end()
+    }
+}
+
+/// Lets commands that still return `Result<_, String>` use `?` on `NbError`.
+impl From<NbError> for String {
+    fn from(err: NbError) -> Self {
+        err.to_string()
+    }
+}
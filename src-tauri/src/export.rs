@@ -0,0 +1,287 @@
+use serde_json::Value;
+
+use crate::cell::CellType;
+use crate::diff::{self, CellDiff, Edit};
+use crate::error::NbError;
+use crate::ipynb;
+
+/// Inlined so the exported file renders offline. Colors follow the light
+/// theme in `styles.css`.
+const HTML_STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", system-ui, sans-serif; margin: 2rem; color: #1d1d1f; background: #fff; }
+h1 { font-size: 1.2rem; font-weight: 600; }
+.files { color: #6e6e73; margin-bottom: 1.5rem; }
+.cell { border: 1px solid #e5e5ea; border-radius: 6px; margin: 0 0 0.75rem; overflow: hidden; }
+.cell-header { font-size: 0.75rem; color: #6e6e73; padding: 0.25rem 0.75rem; border-bottom: 1px solid #e5e5ea; }
+pre { margin: 0; padding: 0.5rem 0.75rem; font: 0.85rem/1.45 ui-monospace, SFMono-Regular, Menlo, monospace; white-space: pre-wrap; word-break: break-word; }
+.added { background: rgba(40, 167, 69, 0.1); }
+.added .cell-header { color: rgba(30, 130, 54, 0.95); }
+.removed { background: rgba(215, 58, 73, 0.08); }
+.removed .cell-header { color: rgba(180, 40, 55, 0.9); }
+.moved { background: rgba(0, 122, 255, 0.06); }
+.moved .cell-header { color: rgba(0, 90, 200, 0.9); }
+.attachments .cell-header { color: rgba(175, 110, 0, 0.95); }
+.modified .sides { display: grid; grid-template-columns: 1fr 1fr; }
+.modified .side-a { background: rgba(215, 58, 73, 0.08); border-right: 1px solid #e5e5ea; }
+.modified .side-b { background: rgba(40, 167, 69, 0.1); }
+"#;
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn push_cell(html: &mut String, class: &str, header: &str, text: &str) {
+    html.push_str(&format!(
+        "<div class=\"cell {}\"><div class=\"cell-header\">{}</div><pre>{}</pre></div>\n",
+        class,
+        header,
+        escape_html(text)
+    ));
+}
+
+/// Renders a cell diff as a standalone HTML document. Added cells are green,
+/// removed cells red, and modified cells show both versions side by side.
+pub fn diff_to_html(diffs: &[CellDiff], name_a: &str, name_b: &str) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!(
+        "<title>{} vs {}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        escape_html(name_a),
+        escape_html(name_b),
+        HTML_STYLE
+    ));
+    html.push_str("<h1>Notebook diff</h1>\n");
+    html.push_str(&format!(
+        "<div class=\"files\">{} &rarr; {}</div>\n",
+        escape_html(name_a),
+        escape_html(name_b)
+    ));
+
+    for diff in diffs {
+        match diff {
+            CellDiff::Unchanged { index, text } => {
+                push_cell(&mut html, "unchanged", &format!("Cell {}", index + 1), text);
+            }
+            CellDiff::Added { index, text } => {
+                push_cell(&mut html, "added", &format!("+ Cell {}", index + 1), text);
+            }
+            CellDiff::Removed { index, text } => {
+                push_cell(
+                    &mut html,
+                    "removed",
+                    &format!("&minus; Cell {}", index + 1),
+                    text,
+                );
+            }
+            CellDiff::Moved { from, to, text } => {
+                push_cell(
+                    &mut html,
+                    "moved",
+                    &format!("Cell {} &rarr; {} (moved)", from + 1, to + 1),
+                    text,
+                );
+            }
+            CellDiff::AttachmentChanged {
+                index_a,
+                index_b,
+                text,
+                ..
+            } => {
+                push_cell(
+                    &mut html,
+                    "attachments",
+                    &format!(
+                        "Cell {} &rarr; {} (attachments changed)",
+                        index_a + 1,
+                        index_b + 1
+                    ),
+                    text,
+                );
+            }
+            CellDiff::Modified {
+                index_a,
+                index_b,
+                text_a,
+                text_b,
+                ..
+            } => {
+                html.push_str(&format!(
+                    "<div class=\"cell modified\"><div class=\"cell-header\">Cell {} &rarr; {}</div>\
+                     <div class=\"sides\"><pre class=\"side-a\">{}</pre><pre class=\"side-b\">{}</pre></div></div>\n",
+                    index_a + 1,
+                    index_b + 1,
+                    escape_html(text_a),
+                    escape_html(text_b)
+                ));
+            }
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// A code fence long enough that no backtick run inside `text` closes it.
+fn fence_for(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn push_fenced(markdown: &mut String, info: &str, text: &str) {
+    let fence = fence_for(text);
+    markdown.push_str(&format!(
+        "{}{}\n{}\n{}\n\n",
+        fence,
+        info,
+        text.trim_end_matches('\n'),
+        fence
+    ));
+}
+
+/// Flattens a notebook to Markdown. Markdown and raw cells are copied
+/// verbatim; code cells become fenced blocks tagged with the notebook's
+/// language, each followed by its text outputs in untagged fences when
+/// `include_outputs` is set.
+pub fn notebook_to_markdown(notebook: &Value, include_outputs: bool) -> Result<String, NbError> {
+    let language = ipynb::language(notebook).unwrap_or_default();
+    let cells = ipynb::cells(notebook)?;
+    let mut markdown = String::new();
+
+    for (cell, raw) in cells.iter().zip(ipynb::cell_values(notebook)) {
+        match cell.cell_type {
+            CellType::Markdown | CellType::Raw => {
+                if !cell.source.trim().is_empty() {
+                    markdown.push_str(cell.source.trim_end_matches('\n'));
+                    markdown.push_str("\n\n");
+                }
+            }
+            CellType::Code => {
+                push_fenced(&mut markdown, &language, &cell.source);
+                if include_outputs {
+                    for text in ipynb::output_texts(raw) {
+                        push_fenced(&mut markdown, "", &text);
+                    }
+                }
+            }
+        }
+    }
+
+    let trimmed = markdown.trim_end().len();
+    markdown.truncate(trimmed);
+    markdown.push('\n');
+    Ok(markdown)
+}
+
+/// Lines of every cell joined into one file, each cell preceded by a
+/// `# --- cell N ---` marker (0-based, matching the cell indices elsewhere).
+fn marked_lines(cells: &[String]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (i, source) in cells.iter().enumerate() {
+        lines.push(format!("# --- cell {} ---", i));
+        lines.extend(source.lines().map(str::to_string));
+    }
+    lines
+}
+
+/// Start and length of one side of a hunk in `@@` notation, where an empty
+/// range starts at the line before it.
+fn hunk_range(start: usize, len: usize) -> String {
+    if len == 0 {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, len)
+    }
+}
+
+/// Renders the cell sources of two notebooks as a unified diff with
+/// `context` lines around each change. Identical inputs give an empty
+/// string, as `diff -u` prints nothing.
+pub fn unified_diff(
+    a: &[String],
+    b: &[String],
+    name_a: &str,
+    name_b: &str,
+    context: usize,
+) -> String {
+    let a_lines = marked_lines(a);
+    let b_lines = marked_lines(b);
+    let edits = diff::myers(&a_lines, &b_lines);
+
+    // Line numbers on each side before every edit
+    let mut positions = Vec::with_capacity(edits.len());
+    let (mut line_a, mut line_b) = (0, 0);
+    for edit in &edits {
+        positions.push((line_a, line_b));
+        match edit {
+            Edit::Equal(..) => {
+                line_a += 1;
+                line_b += 1;
+            }
+            Edit::Delete(_) => line_a += 1,
+            Edit::Insert(_) => line_b += 1,
+        }
+    }
+
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(..)))
+        .map(|(i, _)| i)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", name_a, name_b);
+    let mut next = 0;
+    while next < changes.len() {
+        let first = changes[next];
+        let mut last = first;
+        // Changes whose context would touch or overlap share a hunk
+        while next + 1 < changes.len() && changes[next + 1] - last <= 2 * context + 1 {
+            next += 1;
+            last = changes[next];
+        }
+        next += 1;
+
+        let start = first.saturating_sub(context);
+        let end = (last + context + 1).min(edits.len());
+        let hunk = &edits[start..end];
+        let len_a = hunk
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Insert(_)))
+            .count();
+        let len_b = hunk
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Delete(_)))
+            .count();
+        let (start_a, start_b) = positions[start];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(start_a, len_a),
+            hunk_range(start_b, len_b)
+        ));
+        for edit in hunk {
+            let (prefix, line) = match *edit {
+                Edit::Equal(i, _) => (' ', &a_lines[i]),
+                Edit::Delete(i) => ('-', &a_lines[i]),
+                Edit::Insert(j) => ('+', &b_lines[j]),
+            };
+            out.push(prefix);
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
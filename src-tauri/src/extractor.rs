@@ -0,0 +1,292 @@
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use crate::cache::InputCache;
+use crate::cell::Cell;
+use crate::error::NbError;
+use crate::{ipynb, percent, remote};
+
+/// Turns a local notebook file into its input cells, and downloads remote
+/// ones. Commands take the extractor from managed state (`Box<dyn Extractor>`)
+/// and hand it down as `&dyn Extractor`, so the extraction, diff and merge
+/// helpers never need a Tauri app and can run against canned cells.
+pub trait Extractor: Send + Sync {
+    fn extract<'a>(
+        &'a self,
+        path: &'a str,
+        timeout: Duration,
+        cancel: Option<&'a CancellationToken>,
+    ) -> BoxFuture<'a, Result<Vec<String>, NbError>>;
+
+    /// Where `extract_inputs` remembers results for local files. `None`
+    /// extracts every time.
+    fn cache(&self) -> Option<&InputCache> {
+        None
+    }
+
+    /// Downloads an `http(s)://` notebook.
+    fn fetch<'a>(
+        &'a self,
+        url: &'a str,
+        timeout: Duration,
+    ) -> BoxFuture<'a, Result<Vec<u8>, NbError>> {
+        Box::pin(remote::fetch(url, timeout))
+    }
+}
+
+pub fn has_extension(path: &str, extension: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+pub fn is_ipynb(path: &str) -> bool {
+    has_extension(path, "ipynb")
+}
+
+/// Plain `.py` files are treated as jupytext percent scripts.
+pub fn is_percent_script(path: &str) -> bool {
+    has_extension(path, "py")
+}
+
+pub fn read_percent_script(path: &str) -> Result<Vec<Cell>, NbError> {
+    if !Path::new(path).exists() {
+        return Err(NbError::FileNotFound {
+            path: path.to_string(),
+        });
+    }
+    let contents = std::fs::read(path).map_err(|e| NbError::Io {
+        path: path.to_string(),
+        detail: e.to_string(),
+    })?;
+    parse_percent_script(&contents)
+}
+
+/// Parses percent-script contents that are not on disk, such as a download or
+/// a committed revision.
+pub fn parse_percent_script(contents: &[u8]) -> Result<Vec<Cell>, NbError> {
+    let text = std::str::from_utf8(contents).map_err(|e| NbError::Encoding {
+        offset: e.valid_up_to(),
+    })?;
+    Ok(percent::parse(text))
+}
+
+/// Writes `contents` to a temporary file that keeps the extension of
+/// `original`, since the extractor dispatches on file type. The file is
+/// deleted when the returned path is dropped.
+pub fn write_temp_notebook(contents: &[u8], original: &str) -> Result<tempfile::TempPath, NbError> {
+    let suffix = Path::new(original)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| format!(".{}", ext))
+        .unwrap_or_default();
+    let tmp_dir = std::env::temp_dir().display().to_string();
+    let io_err = |e: std::io::Error| NbError::Io {
+        path: tmp_dir.clone(),
+        detail: e.to_string(),
+    };
+    let mut file = tempfile::Builder::new()
+        .prefix("nbdiff-")
+        .suffix(&suffix)
+        .tempfile()
+        .map_err(io_err)?;
+    file.write_all(contents).map_err(io_err)?;
+    Ok(file.into_temp_path())
+}
+
+/// Parses extractor JSON straight from the raw bytes, so invalid UTF-8 is an
+/// `Encoding` error rather than being replaced and then parsed as garbage.
+pub fn parse_sidecar_json<T: serde::de::DeserializeOwned>(stdout: &[u8]) -> Result<T, NbError> {
+    serde_json::from_slice(stdout).map_err(|e| match std::str::from_utf8(stdout) {
+        Err(utf8) => NbError::Encoding {
+            offset: utf8.valid_up_to(),
+        },
+        Ok(_) => NbError::Parse {
+            detail: e.to_string(),
+        },
+    })
+}
+
+/// Extracts inputs from a local path or an `http(s)://` URL. Remote notebooks
+/// are downloaded to a temporary file that is removed once extraction ends.
+pub async fn extract_inputs(
+    extractor: &dyn Extractor,
+    path: &str,
+    timeout: Duration,
+    cancel: Option<&CancellationToken>,
+) -> Result<Vec<String>, NbError> {
+    let cells = extract_input_cells(extractor, path, timeout, cancel).await?;
+    Ok(cells.into_iter().map(|cell| cell.source).collect())
+}
+
+/// `extract_inputs` as cells, so their hashes can be cached and reused.
+async fn extract_input_cells(
+    extractor: &dyn Extractor,
+    path: &str,
+    timeout: Duration,
+    cancel: Option<&CancellationToken>,
+) -> Result<Vec<Cell>, NbError> {
+    if remote::is_remote(path) {
+        let body = extractor.fetch(path, timeout).await?;
+        let name = remote::file_name(path);
+        if is_percent_script(&name) {
+            return parse_percent_script(&body);
+        }
+        let tmp = write_temp_notebook(&body, &name)?;
+        let inputs = extractor
+            .extract(&tmp.to_string_lossy(), timeout, cancel)
+            .await?;
+        return Ok(inputs.into_iter().map(Cell::code).collect());
+    }
+
+    if is_percent_script(path) {
+        return read_percent_script(path);
+    }
+
+    let Some(cache) = extractor.cache() else {
+        let inputs = extractor.extract(path, timeout, cancel).await?;
+        return Ok(inputs.into_iter().map(Cell::code).collect());
+    };
+    // Without an mtime there is nothing to validate a cache entry against
+    let mtime = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    if let Some(cells) = mtime.and_then(|mtime| cache.get(path, mtime)) {
+        return Ok(cells);
+    }
+
+    let inputs = extractor.extract(path, timeout, cancel).await?;
+    let cells: Vec<Cell> = inputs.into_iter().map(Cell::code).collect();
+    if let Some(mtime) = mtime {
+        cache.insert(path, mtime, cells.clone());
+    }
+    Ok(cells)
+}
+
+/// Reads a Jupyter notebook from disk or, for an `http(s)://` URL, downloads
+/// it first.
+pub async fn read_ipynb(
+    extractor: &dyn Extractor,
+    path: &str,
+    timeout: Duration,
+) -> Result<Value, NbError> {
+    if !remote::is_remote(path) {
+        return ipynb::read_notebook(path);
+    }
+    let body = extractor.fetch(path, timeout).await?;
+    ipynb::parse_notebook(&body)
+}
+
+/// Like `extract_inputs`, but keeps each cell's type and execution count.
+/// Jupyter notebooks, local or remote, are read directly; anything else goes
+/// through the extractor and comes back as code cells.
+pub async fn extract_cells(
+    extractor: &dyn Extractor,
+    path: &str,
+    timeout: Duration,
+) -> Result<Vec<Cell>, NbError> {
+    if is_ipynb(path) {
+        let notebook = read_ipynb(extractor, path, timeout).await?;
+        return ipynb::cells(&notebook);
+    }
+    extract_input_cells(extractor, path, timeout, None).await
+}
+
+/// The sources of `extract_cells`, for callers that only need the text.
+pub async fn extract_sources(
+    extractor: &dyn Extractor,
+    path: &str,
+    timeout: Duration,
+) -> Result<Vec<String>, NbError> {
+    let cells = extract_cells(extractor, path, timeout).await?;
+    Ok(cells.into_iter().map(|cell| cell.source).collect())
+}
+
+/// Serves canned inputs by path and canned downloads by URL, and reports
+/// every other path as missing.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MockExtractor {
+    notebooks: std::collections::HashMap<String, Vec<String>>,
+    downloads: std::collections::HashMap<String, Vec<u8>>,
+}
+
+#[cfg(test)]
+impl MockExtractor {
+    pub fn with(mut self, path: &str, inputs: &[&str]) -> Self {
+        let inputs = inputs.iter().map(|input| input.to_string()).collect();
+        self.notebooks.insert(path.to_string(), inputs);
+        self
+    }
+
+    pub fn with_download(mut self, url: &str, body: &[u8]) -> Self {
+        self.downloads.insert(url.to_string(), body.to_vec());
+        self
+    }
+}
+
+#[cfg(test)]
+impl Extractor for MockExtractor {
+    fn extract<'a>(
+        &'a self,
+        path: &'a str,
+        _timeout: Duration,
+        _cancel: Option<&'a CancellationToken>,
+    ) -> BoxFuture<'a, Result<Vec<String>, NbError>> {
+        let result = self
+            .notebooks
+            .get(path)
+            .cloned()
+            .ok_or_else(|| NbError::FileNotFound {
+                path: path.to_string(),
+            });
+        Box::pin(async move { result })
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        url: &'a str,
+        _timeout: Duration,
+    ) -> BoxFuture<'a, Result<Vec<u8>, NbError>> {
+        let result = self
+            .downloads
+            .get(url)
+            .cloned()
+            .ok_or_else(|| NbError::Http {
+                url: url.to_string(),
+                status: Some(404),
+                detail: "server responded with 404 Not Found".to_string(),
+            });
+        Box::pin(async move { result })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_utf8_is_an_encoding_error() {
+        let result = parse_sidecar_json::<Vec<String>>(b"[\"\xff\"]");
+        assert!(matches!(result, Err(NbError::Encoding { offset: 2 })));
+    }
+
+    #[test]
+    fn percent_script_contents_are_parsed_without_the_extractor() {
+        let cells = parse_percent_script(b"# %%\nx = 1\n\n# %%\ny = 2\n").unwrap();
+        assert_eq!(cells.len(), 2);
+        assert!(matches!(
+            parse_percent_script(b"# %%\n\xff"),
+            Err(NbError::Encoding { offset: 5 })
+        ));
+    }
+
+    #[test]
+    fn malformed_json_is_a_parse_error() {
+        let result = parse_sidecar_json::<Vec<String>>(b"[\"x\"");
+        assert!(matches!(result, Err(NbError::Parse { .. })));
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::error::NbError;
+
+/// Files treated as notebooks when walking a folder.
+const NOTEBOOK_EXTENSIONS: &[&str] = &["nb", "ipynb", "py"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileStatus {
+    Added,
+    Removed,
+    Modified,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiffEntry {
+    /// Relative to the compared folders, with `/` separators on every platform.
+    pub rel_path: String,
+    pub status: FileStatus,
+}
+
+fn collect(root: &Path, dir: &Path, found: &mut BTreeSet<String>) -> Result<(), NbError> {
+    let io_err = |e: std::io::Error| NbError::Io {
+        path: dir.display().to_string(),
+        detail: e.to_string(),
+    };
+    for entry in std::fs::read_dir(dir).map_err(io_err)? {
+        let entry = entry.map_err(io_err)?;
+        let path = entry.path();
+        // Symlinked folders are not followed, so links cannot loop
+        if entry.file_type().map_err(io_err)?.is_dir() {
+            collect(root, &path, found)?;
+            continue;
+        }
+        let is_notebook = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| NOTEBOOK_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+        if let (true, Ok(rel)) = (is_notebook, path.strip_prefix(root)) {
+            let parts: Vec<_> = rel.iter().map(|part| part.to_string_lossy()).collect();
+            found.insert(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+/// Relative paths of every notebook under `dir`, recursing into subfolders.
+pub fn notebooks_in(dir: &str) -> Result<BTreeSet<String>, NbError> {
+    let root = Path::new(dir);
+    if !root.is_dir() {
+        return Err(NbError::FileNotFound {
+            path: dir.to_string(),
+        });
+    }
+    let mut found = BTreeSet::new();
+    collect(root, root, &mut found)?;
+    Ok(found)
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+use std::process::Output;
+
+use crate::error::NbError;
+
+async fn git(dir: &Path, args: &[&str]) -> Result<Output, NbError> {
+    tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| NbError::Git {
+            detail: format!("could not run git: {}", e),
+        })
+}
+
+/// Returns the contents of `path` as committed at `rev`, or `None` if the file
+/// did not exist at that revision.
+pub async fn show_at_rev(path: &str, rev: &str) -> Result<Option<Vec<u8>>, NbError> {
+    if rev.is_empty() || rev.starts_with('-') {
+        return Err(NbError::Git {
+            detail: format!("invalid revision: {:?}", rev),
+        });
+    }
+
+    let file = Path::new(path)
+        .canonicalize()
+        .map_err(|_| NbError::FileNotFound {
+            path: path.to_string(),
+        })?;
+    let (Some(dir), Some(name)) = (file.parent(), file.file_name().and_then(|n| n.to_str())) else {
+        return Err(NbError::Git {
+            detail: format!("cannot resolve {} inside a repository", path),
+        });
+    };
+
+    let inside = git(dir, &["rev-parse", "--is-inside-work-tree"]).await?;
+    if !inside.status.success() {
+        return Err(NbError::Git {
+            detail: format!("{} is not inside a git repository", path),
+        });
+    }
+
+    let commit = format!("{}^{{commit}}", rev);
+    let verify = git(dir, &["rev-parse", "--verify", "--quiet", &commit]).await?;
+    if !verify.status.success() {
+        return Err(NbError::Git {
+            detail: format!("unknown revision: {}", rev),
+        });
+    }
+
+    // `./name` makes git resolve the path relative to `dir` instead of the repo root
+    let spec = format!("{}:./{}", rev, name);
+    let exists = git(dir, &["cat-file", "-e", &spec]).await?;
+    if !exists.status.success() {
+        return Ok(None);
+    }
+
+    let shown = git(dir, &["show", &spec]).await?;
+    if !shown.status.success() {
+        return Err(NbError::Git {
+            detail: String::from_utf8_lossy(&shown.stderr).trim().to_string(),
+        });
+    }
+    Ok(Some(shown.stdout))
+}
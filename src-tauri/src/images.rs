@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use base64::Engine;
+use serde_json::Value;
+use tauri::Manager;
+
+use crate::error::NbError;
+use crate::ipynb;
+
+const IMAGE_DIR: &str = "images";
+
+/// Output MIME types written out as files, with the extension for each.
+const IMAGE_TYPES: &[(&str, &str)] = &[("image/png", "png"), ("image/jpeg", "jpg")];
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageRef {
+    pub cell_index: usize,
+    pub output_index: usize,
+    pub file_path: String,
+    pub mime: String,
+}
+
+fn image_dir(app: &tauri::AppHandle) -> Result<PathBuf, NbError> {
+    let dir = app.path().app_cache_dir().map_err(|e| NbError::Io {
+        path: "app cache dir".to_string(),
+        detail: e.to_string(),
+    })?;
+    Ok(dir.join(IMAGE_DIR))
+}
+
+/// Decodes a base64 payload, ignoring the line breaks Jupyter may store in it.
+fn decode(payload: &str) -> Result<Vec<u8>, NbError> {
+    let compact: String = payload.split_whitespace().collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(compact)
+        .map_err(|e| NbError::Parse {
+            detail: format!("Invalid base64 image data: {}", e),
+        })
+}
+
+/// Writes every PNG and JPEG output in `notebook` under the app cache dir.
+/// Files are named by a hash of their contents, so an image seen before is
+/// not written again.
+pub fn extract(app: &tauri::AppHandle, notebook: &Value) -> Result<Vec<ImageRef>, NbError> {
+    let dir = image_dir(app)?;
+    let io_err = |path: &PathBuf, e: std::io::Error| NbError::Io {
+        path: path.display().to_string(),
+        detail: e.to_string(),
+    };
+    std::fs::create_dir_all(&dir).map_err(|e| io_err(&dir, e))?;
+
+    let mut images = Vec::new();
+    for (cell_index, cell) in ipynb::cell_values(notebook).iter().enumerate() {
+        let Some(outputs) = cell.get("outputs").and_then(Value::as_array) else {
+            continue;
+        };
+        for (output_index, output) in outputs.iter().enumerate() {
+            let Some(data) = output.get("data") else {
+                continue;
+            };
+            for (mime, extension) in IMAGE_TYPES {
+                let Some(payload) = data.get(*mime) else {
+                    continue;
+                };
+                let bytes = decode(&ipynb::source_text(payload))?;
+                let file = dir.join(format!("{:016x}.{}", seahash::hash(&bytes), extension));
+                if !file.exists() {
+                    std::fs::write(&file, &bytes).map_err(|e| io_err(&file, e))?;
+                }
+                images.push(ImageRef {
+                    cell_index,
+                    output_index,
+                    file_path: file.display().to_string(),
+                    mime: mime.to_string(),
+                });
+            }
+        }
+    }
+    Ok(images)
+}
+
+/// Deletes all extracted images. A cache that was never created is not an error.
+pub fn clear(app: &tauri::AppHandle) -> Result<(), NbError> {
+    let dir = image_dir(app)?;
+    match std::fs::remove_dir_all(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(NbError::Io {
+            path: dir.display().to_string(),
+            detail: e.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,713 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::cell::{Attachment, Cell, CellType};
+use crate::error::NbError;
+
+/// Kernel and format information from a notebook's top level.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookMeta {
+    pub kernel_name: Option<String>,
+    pub language: Option<String>,
+    pub nbformat: u32,
+    pub nbformat_minor: u32,
+    pub cell_count: usize,
+}
+
+/// Result of `validate`. `problems` lists every issue found, not just the first.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub problems: Vec<String>,
+}
+
+/// The format versions of two notebooks. `compatible` is true only when both
+/// `nbformat` and `nbformat_minor` match, so the JSON can be compared as-is.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatComparison {
+    pub nbformat_a: u32,
+    pub nbformat_minor_a: u32,
+    pub nbformat_b: u32,
+    pub nbformat_minor_b: u32,
+    pub compatible: bool,
+}
+
+/// The nbformat 4 minor version that introduced cell `id`s.
+const CELL_ID_MINOR: u32 = 5;
+
+/// Reads and parses a Jupyter notebook. Object key order is preserved so the
+/// notebook can be written back without reshuffling its metadata.
+pub fn read_notebook(path: &str) -> Result<Value, NbError> {
+    if !Path::new(path).exists() {
+        return Err(NbError::FileNotFound {
+            path: path.to_string(),
+        });
+    }
+    let contents = std::fs::read_to_string(path).map_err(|e| NbError::Io {
+        path: path.to_string(),
+        detail: e.to_string(),
+    })?;
+    serde_json::from_str(&contents).map_err(|e| NbError::Parse {
+        detail: e.to_string(),
+    })
+}
+
+/// Parses notebook contents that are not on disk, such as a download or a
+/// committed revision.
+pub fn parse_notebook(contents: &[u8]) -> Result<Value, NbError> {
+    serde_json::from_slice(contents).map_err(|e| NbError::Parse {
+        detail: e.to_string(),
+    })
+}
+
+/// Serializes a notebook the way Jupyter does: one-space indentation,
+/// non-ASCII left unescaped, and a trailing newline.
+pub fn to_json(notebook: &Value) -> Result<String, NbError> {
+    let mut buf = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+    serde::Serialize::serialize(notebook, &mut serializer).map_err(|e| NbError::Parse {
+        detail: e.to_string(),
+    })?;
+    buf.push(b'\n');
+    String::from_utf8(buf).map_err(|e| NbError::Parse {
+        detail: e.to_string(),
+    })
+}
+
+pub fn write_notebook(path: &str, notebook: &Value) -> Result<(), NbError> {
+    let json = to_json(notebook)?;
+    std::fs::write(path, json).map_err(|e| NbError::Io {
+        path: path.to_string(),
+        detail: e.to_string(),
+    })
+}
+
+/// Empties `outputs` and nulls `execution_count` if `cell` is a code cell.
+fn clear_cell_outputs(cell: &mut Value) {
+    if cell.get("cell_type").and_then(Value::as_str) != Some("code") {
+        return;
+    }
+    if let Some(cell) = cell.as_object_mut() {
+        cell.insert("outputs".to_string(), Value::Array(Vec::new()));
+        cell.insert("execution_count".to_string(), Value::Null);
+    }
+}
+
+/// Empties `outputs` and nulls `execution_count` on every code cell. All other
+/// content, including cell order and notebook metadata, is left untouched.
+pub fn clear_outputs(notebook: &mut Value) {
+    let Some(cells) = notebook.get_mut("cells").and_then(Value::as_array_mut) else {
+        return;
+    };
+    cells.iter_mut().for_each(clear_cell_outputs);
+}
+
+/// The notebook's cell list, with `index` checked to be in range.
+fn cells_with_index(notebook: &mut Value, index: usize) -> Result<&mut Vec<Value>, NbError> {
+    let cells = notebook
+        .get_mut("cells")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| NbError::Parse {
+            detail: "Notebook has no cells array".to_string(),
+        })?;
+    if index >= cells.len() {
+        return Err(NbError::CellOutOfRange {
+            index,
+            count: cells.len(),
+        });
+    }
+    Ok(cells)
+}
+
+/// Deletes the cell at `index`, leaving every other cell as it was.
+pub fn remove_cell(notebook: &mut Value, index: usize) -> Result<(), NbError> {
+    cells_with_index(notebook, index)?.remove(index);
+    Ok(())
+}
+
+/// Clears the outputs of the cell at `index` as `clear_outputs` does. Cells
+/// other than code cells have no outputs and are left unchanged.
+pub fn clear_cell_output(notebook: &mut Value, index: usize) -> Result<(), NbError> {
+    clear_cell_outputs(&mut cells_with_index(notebook, index)?[index]);
+    Ok(())
+}
+
+fn sort_keys(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = std::mem::take(map).into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (key, mut value) in entries {
+                sort_keys(&mut value);
+                map.insert(key, value);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(sort_keys),
+        _ => {}
+    }
+}
+
+/// Rewrites a notebook into a canonical form so that notebooks differing
+/// only cosmetically serialize identically:
+///
+/// - every object's keys are sorted, as Jupyter itself writes them;
+/// - sources use `\n` line endings and the list-of-lines form;
+/// - multiline output fields (stream `text` and non-JSON `data` entries) use
+///   the list-of-lines form, with their line endings kept as they are;
+/// - execution counts are cleared, on cells and on `execute_result` outputs;
+/// - cells are given ids `cell-0`, `cell-1`, ... when the format has ids
+///   (4.5 and later) or the cell already had one.
+///
+/// A multiline field that is neither a string nor a list of strings is an
+/// error rather than being dropped.
+pub fn normalize(notebook: &mut Value) -> Result<(), NbError> {
+    let has_ids = format_version(notebook)? >= (4, CELL_ID_MINOR);
+    if let Some(cells) = notebook.get_mut("cells").and_then(Value::as_array_mut) {
+        for (i, cell) in cells.iter_mut().enumerate() {
+            let Some(cell) = cell.as_object_mut() else {
+                continue;
+            };
+            if let Some(source) = cell.get("source") {
+                let text = multiline(source, || format!("cell {} `source`", i))?
+                    .replace("\r\n", "\n")
+                    .replace('\r', "\n");
+                cell.insert("source".to_string(), source_lines(&text));
+            }
+            if cell.contains_key("execution_count") {
+                cell.insert("execution_count".to_string(), Value::Null);
+            }
+            if has_ids || cell.contains_key("id") {
+                cell.insert("id".to_string(), Value::String(format!("cell-{}", i)));
+            }
+            let outputs = cell.get_mut("outputs").and_then(Value::as_array_mut);
+            for (j, output) in outputs.into_iter().flatten().enumerate() {
+                if let Some(count) = output.get_mut("execution_count") {
+                    *count = Value::Null;
+                }
+                normalize_output_text(output, &format!("cell {} output {}", i, j))?;
+            }
+        }
+    }
+    sort_keys(notebook);
+    Ok(())
+}
+
+/// Rewrites the multiline fields of one output in list-of-lines form. JSON
+/// MIME types hold structured data, not text, and are left alone.
+fn normalize_output_text(output: &mut Value, location: &str) -> Result<(), NbError> {
+    if let Some(text) = output.get_mut("text") {
+        *text = source_lines(&multiline(text, || format!("{} `text`", location))?);
+    }
+    let data = output.get_mut("data").and_then(Value::as_object_mut);
+    for (mime, payload) in data.into_iter().flatten() {
+        if mime.ends_with("json") {
+            continue;
+        }
+        let what = || format!("{} `{}` data", location, mime);
+        *payload = source_lines(&multiline(payload, what)?);
+    }
+    Ok(())
+}
+
+fn format_field(notebook: &Value, key: &str) -> Result<u32, NbError> {
+    notebook
+        .get(key)
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .ok_or_else(|| NbError::Parse {
+            detail: format!("missing or invalid `{}`", key),
+        })
+}
+
+/// The notebook's language from `language_info`, falling back to the
+/// kernelspec's `language`.
+pub fn language(notebook: &Value) -> Option<String> {
+    let meta = notebook.get("metadata");
+    meta.and_then(|m| m.get("language_info"))
+        .and_then(|l| l.get("name"))
+        .or_else(|| {
+            meta.and_then(|m| m.get("kernelspec"))
+                .and_then(|k| k.get("language"))
+        })
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Pulls kernel and format details out of a parsed notebook.
+pub fn metadata(notebook: &Value) -> Result<NotebookMeta, NbError> {
+    let kernel_name = notebook
+        .get("metadata")
+        .and_then(|m| m.get("kernelspec"))
+        .and_then(|k| k.get("name"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let cell_count = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .map_or(0, Vec::len);
+
+    Ok(NotebookMeta {
+        kernel_name,
+        language: language(notebook),
+        nbformat: format_field(notebook, "nbformat")?,
+        nbformat_minor: format_field(notebook, "nbformat_minor")?,
+        cell_count,
+    })
+}
+
+fn format_version(notebook: &Value) -> Result<(u32, u32), NbError> {
+    Ok((
+        format_field(notebook, "nbformat")?,
+        format_field(notebook, "nbformat_minor")?,
+    ))
+}
+
+pub fn compare_formats(a: &Value, b: &Value) -> Result<FormatComparison, NbError> {
+    let (nbformat_a, nbformat_minor_a) = format_version(a)?;
+    let (nbformat_b, nbformat_minor_b) = format_version(b)?;
+    Ok(FormatComparison {
+        nbformat_a,
+        nbformat_minor_a,
+        nbformat_b,
+        nbformat_minor_b,
+        compatible: (nbformat_a, nbformat_minor_a) == (nbformat_b, nbformat_minor_b),
+    })
+}
+
+/// Upconverts whichever of `a` and `b` has the older minor format to the
+/// other's, so format-only changes don't show up in a diff. The rules:
+///
+/// - Only nbformat 4 is converted, and only upwards. Notebooks with different
+///   major versions are left as they are.
+/// - Moving from below 4.5 to 4.5 or later gives every cell that lacks one an
+///   `id`. Ids are taken from the cell's position (`cell-0`, `cell-1`, ...) so
+///   converting the same notebook twice gives the same result.
+/// - `nbformat_minor` is raised to the newer version. Nothing else changes.
+///
+/// Returns whether ids were made up. They never match the real ids on the
+/// other side, so callers should not compare ids when this is true.
+pub fn upconvert_to_match(a: &mut Value, b: &mut Value) -> Result<bool, NbError> {
+    let version_a = format_version(a)?;
+    let version_b = format_version(b)?;
+    if version_a.0 != 4 || version_b.0 != 4 {
+        return Ok(false);
+    }
+    let ids_added = if version_a.1 < version_b.1 {
+        upconvert(a, version_a.1, version_b.1)
+    } else if version_b.1 < version_a.1 {
+        upconvert(b, version_b.1, version_a.1)
+    } else {
+        false
+    };
+    Ok(ids_added)
+}
+
+/// Returns whether cell ids were added.
+fn upconvert(notebook: &mut Value, from_minor: u32, to_minor: u32) -> bool {
+    let adds_ids = from_minor < CELL_ID_MINOR && to_minor >= CELL_ID_MINOR;
+    if adds_ids {
+        if let Some(cells) = notebook.get_mut("cells").and_then(Value::as_array_mut) {
+            for (i, cell) in cells.iter_mut().enumerate() {
+                if let Some(cell) = cell.as_object_mut() {
+                    cell.entry("id")
+                        .or_insert_with(|| Value::String(format!("cell-{}", i)));
+                }
+            }
+        }
+    }
+    if let Some(notebook) = notebook.as_object_mut() {
+        notebook.insert("nbformat_minor".to_string(), Value::from(to_minor));
+    }
+    adds_ids
+}
+
+/// The raw JSON of each cell, or an empty slice if `cells` is missing.
+pub fn cell_values(notebook: &Value) -> &[Value] {
+    notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+/// A multiline string as nbformat stores it: either one string or a list of
+/// lines that already carry their own `\n`s.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum MultilineText {
+    Text(String),
+    Lines(Vec<String>),
+}
+
+impl MultilineText {
+    fn parse(value: &Value) -> Result<String, serde_json::Error> {
+        Ok(match serde::Deserialize::deserialize(value)? {
+            MultilineText::Text(text) => text,
+            MultilineText::Lines(lines) => lines.concat(),
+        })
+    }
+}
+
+/// Joins a multiline field, failing with a `Parse` error naming `what` when it
+/// is malformed.
+fn multiline(value: &Value, what: impl FnOnce() -> String) -> Result<String, NbError> {
+    MultilineText::parse(value).map_err(|_| NbError::Parse {
+        detail: format!("{} is neither a string nor a list of strings", what()),
+    })
+}
+
+/// Joins a `source`-style field, treating anything malformed as empty.
+pub fn source_text(value: &Value) -> String {
+    MultilineText::parse(value).unwrap_or_default()
+}
+
+/// The plain-text rendering of each of a code cell's outputs: stream text,
+/// the `text/plain` form of results and display data, and the traceback of
+/// errors. Outputs with no text form are skipped.
+pub fn output_texts(cell: &Value) -> Vec<String> {
+    let Some(outputs) = cell.get("outputs").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    outputs
+        .iter()
+        .filter_map(|output| match output.get("output_type")?.as_str()? {
+            "stream" => output.get("text").map(source_text),
+            "execute_result" | "display_data" => output
+                .get("data")
+                .and_then(|d| d.get("text/plain"))
+                .map(source_text),
+            "error" => {
+                let traceback = output.get("traceback")?.as_array()?;
+                let lines: Vec<&str> = traceback.iter().filter_map(Value::as_str).collect();
+                Some(lines.join("\n"))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Every image in a code cell's outputs as `(mime type, base64 payload)`.
+pub fn output_images(cell: &Value) -> Vec<(String, String)> {
+    let Some(outputs) = cell.get("outputs").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    outputs
+        .iter()
+        .filter_map(|output| output.get("data").and_then(Value::as_object))
+        .flat_map(|data| {
+            data.iter()
+                .filter(|(mime, _)| mime.starts_with("image/"))
+                .map(|(mime, payload)| (mime.clone(), source_text(payload)))
+        })
+        .collect()
+}
+
+/// The attachments of a cell as sorted `(name, mime)` pairs, one per MIME
+/// type stored under each name.
+pub fn attachments(cell: &Value) -> Vec<Attachment> {
+    let Some(attachments) = cell.get("attachments").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let mut found: Vec<Attachment> = attachments
+        .iter()
+        .flat_map(|(name, bundle)| {
+            bundle
+                .as_object()
+                .into_iter()
+                .flat_map(|bundle| bundle.keys())
+                .map(move |mime| Attachment {
+                    name: name.clone(),
+                    mime: mime.clone(),
+                })
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+pub fn cells(notebook: &Value) -> Result<Vec<Cell>, NbError> {
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| NbError::Parse {
+            detail: "missing `cells` array".to_string(),
+        })?;
+
+    cells
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| {
+            let cell_type = match cell.get("cell_type").and_then(Value::as_str) {
+                Some("code") => CellType::Code,
+                Some("markdown") => CellType::Markdown,
+                Some("raw") => CellType::Raw,
+                other => {
+                    return Err(NbError::Parse {
+                        detail: format!("cell {} has unknown cell_type {:?}", i, other),
+                    })
+                }
+            };
+            let source = match cell.get("source") {
+                Some(source) => MultilineText::parse(source).map_err(|_| NbError::Parse {
+                    detail: format!(
+                        "cell {} has a `source` that is neither a string nor a list of strings",
+                        i
+                    ),
+                })?,
+                None => String::new(),
+            };
+            Ok(Cell::new(
+                cell_type,
+                source,
+                cell.get("execution_count").and_then(Value::as_u64),
+                attachments(cell),
+            ))
+        })
+        .collect()
+}
+
+/// Splits text into the list-of-lines form Jupyter writes, each line but the
+/// last keeping its `\n`.
+fn source_lines(text: &str) -> Value {
+    Value::Array(
+        text.split_inclusive('\n')
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    )
+}
+
+/// Serializes a cell the way Jupyter stores a freshly created one: keys in
+/// alphabetical order, no outputs, and an `id` only when `id` is given.
+fn cell_json(cell: &Cell, id: Option<String>) -> Value {
+    let mut json = serde_json::Map::new();
+    let cell_type = match cell.cell_type {
+        CellType::Code => "code",
+        CellType::Markdown => "markdown",
+        CellType::Raw => "raw",
+    };
+    json.insert("cell_type".to_string(), Value::from(cell_type));
+    if cell.cell_type == CellType::Code {
+        json.insert("execution_count".to_string(), Value::Null);
+    }
+    if let Some(id) = id {
+        json.insert("id".to_string(), Value::String(id));
+    }
+    json.insert(
+        "metadata".to_string(),
+        Value::Object(serde_json::Map::new()),
+    );
+    if cell.cell_type == CellType::Code {
+        json.insert("outputs".to_string(), Value::Array(Vec::new()));
+    }
+    json.insert("source".to_string(), source_lines(&cell.source));
+    Value::Object(json)
+}
+
+/// Replaces the notebook's cells, keeping its top-level metadata and format
+/// version. Cells get ids when the format (4.5 and later) requires them.
+pub fn replace_cells(notebook: &mut Value, cells: &[Cell]) -> Result<(), NbError> {
+    let needs_ids = format_version(notebook)? >= (4, CELL_ID_MINOR);
+    let cells = cells
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| cell_json(cell, needs_ids.then(|| format!("cell-{}", i))))
+        .collect();
+    let Some(notebook) = notebook.as_object_mut() else {
+        return Err(NbError::Parse {
+            detail: "top level must be a JSON object".to_string(),
+        });
+    };
+    notebook.insert("cells".to_string(), Value::Array(cells));
+    Ok(())
+}
+
+/// Checks the parts of the nbformat structure that extraction relies on:
+/// top-level `cells`, `nbformat` and `nbformat_minor`, and a valid
+/// `cell_type` and `source` on every cell. Takes raw bytes so that a file
+/// that is not UTF-8 is reported as a problem like any other.
+pub fn validate(contents: &[u8]) -> ValidationReport {
+    let mut problems = Vec::new();
+
+    match std::str::from_utf8(contents).map(serde_json::from_str::<Value>) {
+        Err(e) => problems.push(format!(
+            "not valid UTF-8: invalid byte at offset {}",
+            e.valid_up_to()
+        )),
+        Ok(Err(e)) => problems.push(format!("not valid JSON: {}", e)),
+        Ok(Ok(Value::Object(notebook))) => {
+            for key in ["nbformat", "nbformat_minor"] {
+                match notebook.get(key) {
+                    None => problems.push(format!("missing `{}`", key)),
+                    Some(v) if !v.is_u64() => {
+                        problems.push(format!("`{}` must be a non-negative integer", key))
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            match notebook.get("cells") {
+                None => problems.push("missing `cells`".to_string()),
+                Some(Value::Array(cells)) => {
+                    for (i, cell) in cells.iter().enumerate() {
+                        validate_cell(i, cell, &mut problems);
+                    }
+                }
+                Some(_) => problems.push("`cells` must be an array".to_string()),
+            }
+        }
+        Ok(Ok(_)) => problems.push("top level must be a JSON object".to_string()),
+    }
+
+    ValidationReport {
+        valid: problems.is_empty(),
+        problems,
+    }
+}
+
+fn validate_cell(index: usize, cell: &Value, problems: &mut Vec<String>) {
+    let Some(cell) = cell.as_object() else {
+        problems.push(format!("cell {}: must be an object", index));
+        return;
+    };
+
+    match cell.get("cell_type") {
+        None => problems.push(format!("cell {}: missing `cell_type`", index)),
+        Some(Value::String(t)) if ["code", "markdown", "raw"].contains(&t.as_str()) => {}
+        Some(other) => problems.push(format!("cell {}: invalid `cell_type` {}", index, other)),
+    }
+
+    match cell.get("source") {
+        None => problems.push(format!("cell {}: missing `source`", index)),
+        Some(Value::String(_)) => {}
+        Some(Value::Array(lines)) if lines.iter().all(Value::is_string) => {}
+        Some(_) => problems.push(format!(
+            "cell {}: `source` must be a string or a list of strings",
+            index
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn string_and_list_sources_give_the_same_cells() {
+        let as_string = json!({
+            "nbformat": 4,
+            "nbformat_minor": 5,
+            "metadata": {},
+            "cells": [
+                {"cell_type": "markdown", "metadata": {}, "source": "# Title\nSome text"},
+                {
+                    "cell_type": "code",
+                    "execution_count": 1,
+                    "metadata": {},
+                    "outputs": [],
+                    "source": "x = 1\nprint(x)\n"
+                }
+            ]
+        });
+        let as_list = json!({
+            "nbformat": 4,
+            "nbformat_minor": 5,
+            "metadata": {},
+            "cells": [
+                {"cell_type": "markdown", "metadata": {}, "source": ["# Title\n", "Some text"]},
+                {
+                    "cell_type": "code",
+                    "execution_count": 1,
+                    "metadata": {},
+                    "outputs": [],
+                    "source": ["x = 1\n", "print(x)\n"]
+                }
+            ]
+        });
+        let cells_a = cells(&as_string).unwrap();
+        assert_eq!(cells_a, cells(&as_list).unwrap());
+        assert_eq!(cells_a[1].source, "x = 1\nprint(x)\n");
+    }
+
+    #[test]
+    fn cosmetic_differences_normalize_identically() {
+        let mut a = json!({
+            "nbformat": 4,
+            "nbformat_minor": 5,
+            "metadata": {"kernelspec": {"name": "python3", "display_name": "Python 3"}},
+            "cells": [{
+                "id": "f00d",
+                "cell_type": "code",
+                "execution_count": 7,
+                "metadata": {},
+                "source": "x = 1\r\nx\r\n",
+                "outputs": [
+                    {"output_type": "stream", "name": "stdout", "text": "one\ntwo\n"},
+                    {
+                        "output_type": "execute_result",
+                        "execution_count": 7,
+                        "metadata": {},
+                        "data": {"text/plain": "1", "application/json": {"v": ["a", "b"]}}
+                    }
+                ]
+            }]
+        });
+        let mut b = json!({
+            "metadata": {"kernelspec": {"display_name": "Python 3", "name": "python3"}},
+            "nbformat_minor": 5,
+            "nbformat": 4,
+            "cells": [{
+                "source": ["x = 1\n", "x\n"],
+                "outputs": [
+                    {"name": "stdout", "output_type": "stream", "text": ["one\n", "two\n"]},
+                    {
+                        "data": {"application/json": {"v": ["a", "b"]}, "text/plain": ["1"]},
+                        "metadata": {},
+                        "execution_count": 3,
+                        "output_type": "execute_result"
+                    }
+                ],
+                "metadata": {},
+                "execution_count": 3,
+                "cell_type": "code",
+                "id": "beef"
+            }]
+        });
+        normalize(&mut a).unwrap();
+        normalize(&mut b).unwrap();
+        assert_eq!(to_json(&a).unwrap(), to_json(&b).unwrap());
+    }
+
+    #[test]
+    fn normalize_rejects_malformed_sources() {
+        let mut notebook = json!({
+            "nbformat": 4,
+            "nbformat_minor": 4,
+            "cells": [{"cell_type": "code", "source": {"not": "text"}}]
+        });
+        assert!(matches!(
+            normalize(&mut notebook),
+            Err(NbError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_utf8_is_a_validation_problem() {
+        let report = validate(b"{\"cells\": [\"\xff\"]}");
+        assert!(!report.valid);
+        assert_eq!(
+            report.problems,
+            ["not valid UTF-8: invalid byte at offset 12"]
+        );
+    }
+
+    #[test]
+    fn malformed_source_is_an_error() {
+        let notebook = json!({"cells": [{"cell_type": "code", "source": 42}]});
+        assert!(matches!(cells(&notebook), Err(NbError::Parse { .. })));
+    }
+}
@@ -1,4 +1,70 @@
+mod cache;
+mod cancel;
+mod cell;
+mod compare;
+mod diff;
+mod error;
+mod export;
+mod extractor;
+mod folders;
+mod git;
+mod images;
+mod ipynb;
+mod merge;
+mod nbconvert;
+mod outputs;
+mod percent;
+mod recents;
+mod remote;
+mod search;
+mod settings;
+mod watch;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use cache::InputCache;
+use cancel::Extractions;
+use cell::Cell;
+use diff::{CellDiff, DiffOptions, DiffStats};
+use error::NbError;
+use extractor::{parse_sidecar_json, Extractor};
+use folders::FileDiffEntry;
+use futures::future::BoxFuture;
+use futures::stream::{self, StreamExt};
+use images::ImageRef;
+use ipynb::{FormatComparison, NotebookMeta, ValidationReport};
+use merge::MergeResult;
+use outputs::{OutputDiff, OutputSummary};
+use search::SearchHit;
+use settings::Settings;
+use tauri::async_runtime::Receiver;
+use tauri::{Emitter, Manager, RunEvent};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
 use tauri_plugin_shell::ShellExt;
+use tokio_util::sync::CancellationToken;
+
+/// Name of the bundled extractor sidecar.
+const SIDECAR_NAME: &str = "extract-inputs";
+
+/// Environment variable that, when set, points at an extractor binary to run
+/// instead of the bundled sidecar (e.g. a local development build).
+const EXTRACTOR_ENV: &str = "NBDIFF_EXTRACTOR";
+
+/// How many times to try starting the sidecar before giving up. The delay
+/// between attempts starts at `SPAWN_RETRY_DELAY` and doubles each time.
+const SPAWN_ATTEMPTS: u32 = 3;
+const SPAWN_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Log file name (without extension) inside the app log dir.
+const LOG_FILE_NAME: &str = "nbdiff";
+
+/// Log files are rotated at this size, keeping `LOG_KEEP_FILES` old ones.
+const LOG_MAX_FILE_SIZE: u128 = 5 * 1024 * 1024;
+const LOG_KEEP_FILES: usize = 2;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 struct BatchFileResult {
@@ -17,27 +83,679 @@ struct BatchResult {
     failed: usize,
 }
 
-#[tauri::command]
-async fn extract_inputs(app: tauri::AppHandle, path: String) -> Result<Vec<String>, String> {
+/// Extraction result for one of the paths passed to `extract_inputs_many`.
+#[derive(Debug, serde::Serialize)]
+struct PathInputs {
+    path: String,
+    inputs: Vec<String>,
+    error: Option<NbError>,
+}
+
+/// Payload of the `extract-progress` event emitted by `extract_inputs_many`.
+#[derive(Debug, Clone, serde::Serialize)]
+struct ExtractProgress {
+    completed: usize,
+    total: usize,
+    #[serde(rename = "currentPath")]
+    current_path: String,
+}
+
+/// Payload of the `cell-extracted` event emitted by `extract_inputs_streaming`.
+#[derive(Debug, Clone, serde::Serialize)]
+struct CellExtracted {
+    path: String,
+    index: usize,
+    source: String,
+}
+
+/// How long each stage of one extraction took, in milliseconds. `spawn_ms`
+/// includes any spawn retries.
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtractTiming {
+    spawn_ms: u64,
+    read_ms: u64,
+    parse_ms: u64,
+    total_ms: u64,
+}
+
+/// A single output cell. Graphics are not rendered to text; instead they come
+/// back in `mime_bundle` keyed by MIME type (e.g. `image/png` -> base64).
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct CellOutput {
+    #[serde(rename = "outputType")]
+    output_type: String,
+    text: Option<String>,
+    #[serde(rename = "mimeBundle")]
+    mime_bundle: Option<HashMap<String, String>>,
+}
+
+/// Builds and spawns the extractor once. Only fails with `SidecarSpawn`.
+fn spawn_sidecar(
+    app: &tauri::AppHandle,
+    args: &[&str],
+    attempts: u32,
+) -> Result<(Receiver<CommandEvent>, CommandChild), NbError> {
     let shell = app.shell();
-    let output = shell
-        .sidecar("extract-inputs")
-        .map_err(|e| format!("Failed to create sidecar command: {}", e))?
-        .args(["--single", &path])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run sidecar: {}", e))?;
+    let (command, program) = match std::env::var(EXTRACTOR_ENV) {
+        Ok(program) if !program.is_empty() => (shell.command(&program), program),
+        _ => {
+            let command = shell
+                .sidecar(SIDECAR_NAME)
+                .map_err(|e| NbError::SidecarSpawn {
+                    detail: format!("failed to create sidecar command: {}", e),
+                    attempts,
+                })?;
+            (command, SIDECAR_NAME.to_string())
+        }
+    };
+    command
+        .args(args)
+        .set_raw_out(true)
+        .spawn()
+        .map_err(|e| NbError::SidecarSpawn {
+            detail: format!("could not spawn {}: {}", program, e),
+            attempts,
+        })
+}
+
+/// Runs the extractor, passing each chunk of stdout to `on_stdout` as it
+/// arrives. Returns the exit code and everything written to stderr, and fills
+/// in the spawn and read times of `timing`. An error from `on_stdout`, a
+/// timeout or a cancellation kills the process.
+async fn drive_sidecar(
+    app: &tauri::AppHandle,
+    args: &[&str],
+    timeout: Option<Duration>,
+    cancel: Option<&CancellationToken>,
+    timing: &mut ExtractTiming,
+    mut on_stdout: impl FnMut(Vec<u8>) -> Result<(), NbError>,
+) -> Result<(Option<i32>, Vec<u8>), NbError> {
+    let spawning = Instant::now();
+    // Spawning occasionally fails transiently (antivirus scans, slow disks).
+    // Exits and bad output are never retried, only failures to start.
+    let mut attempt = 1;
+    let (mut rx, child) = loop {
+        match spawn_sidecar(app, args, attempt) {
+            Ok(spawned) => break spawned,
+            Err(e) if attempt < SPAWN_ATTEMPTS => {
+                let delay = SPAWN_RETRY_DELAY * 2u32.pow(attempt - 1);
+                log::warn!(
+                    "Sidecar spawn attempt {} of {} failed, retrying in {} ms: {}",
+                    attempt,
+                    SPAWN_ATTEMPTS,
+                    delay.as_millis(),
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    };
+    timing.spawn_ms = spawning.elapsed().as_millis() as u64;
+
+    let reading = Instant::now();
+    let collect = async {
+        let mut code = None;
+        let mut stderr = Vec::new();
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stdout(bytes) => on_stdout(bytes)?,
+                CommandEvent::Stderr(bytes) => stderr.extend(bytes),
+                CommandEvent::Terminated(payload) => code = payload.code,
+                _ => {}
+            }
+        }
+        Ok((code, stderr))
+    };
+
+    let expired = async {
+        match timeout {
+            Some(limit) => tokio::time::sleep(limit).await,
+            None => std::future::pending().await,
+        }
+    };
+    let cancelled = async {
+        match cancel {
+            Some(token) => token.cancelled().await,
+            None => std::future::pending().await,
+        }
+    };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("extract-inputs failed: {}", stderr));
+    // Dropping the receiver does not stop the process, so kill it explicitly
+    let failure = tokio::select! {
+        collected = collect => match collected {
+            Ok(collected) => {
+                timing.read_ms = reading.elapsed().as_millis() as u64;
+                return Ok(collected);
+            }
+            Err(e) => e,
+        },
+        _ = expired => NbError::Timeout {
+            timeout_ms: timeout.unwrap_or_default().as_millis() as u64,
+        },
+        _ = cancelled => NbError::Cancelled,
+    };
+    if let Err(e) = child.kill() {
+        log::warn!("Failed to kill sidecar after {}: {}", failure.kind(), e);
     }
+    Err(failure)
+}
+
+async fn run_sidecar(
+    app: &tauri::AppHandle,
+    args: &[&str],
+    timeout: Option<Duration>,
+    cancel: Option<&CancellationToken>,
+) -> Result<Vec<u8>, NbError> {
+    let (stdout, _) = run_sidecar_timed(app, args, timeout, cancel).await?;
+    Ok(stdout)
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let inputs: Vec<String> =
-        serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+async fn run_sidecar_timed(
+    app: &tauri::AppHandle,
+    args: &[&str],
+    timeout: Option<Duration>,
+    cancel: Option<&CancellationToken>,
+) -> Result<(Vec<u8>, ExtractTiming), NbError> {
+    let mut timing = ExtractTiming::default();
+    let mut stdout = Vec::new();
+    let (code, stderr) = drive_sidecar(app, args, timeout, cancel, &mut timing, |bytes| {
+        stdout.extend(bytes);
+        Ok(())
+    })
+    .await?;
 
-    Ok(inputs)
+    if code != Some(0) {
+        return Err(NbError::sidecar_failed(
+            code,
+            String::from_utf8_lossy(&stdout).into_owned(),
+            String::from_utf8_lossy(&stderr).into_owned(),
+        ));
+    }
+
+    Ok((stdout, timing))
+}
+
+/// Runs the extractor in `--stream` mode, where it prints one JSON string per
+/// input cell per line, and emits `cell-extracted` for each cell as soon as
+/// its line is complete. Only the current partial line is held in memory.
+async fn stream_sidecar_inputs(
+    app: &tauri::AppHandle,
+    path: &str,
+    timeout: Option<Duration>,
+) -> Result<usize, NbError> {
+    let mut timing = ExtractTiming::default();
+    let mut pending = Vec::new();
+    let mut count = 0;
+    let mut emit_line = |line: &[u8]| -> Result<(), NbError> {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return Ok(());
+        }
+        let source: String = parse_sidecar_json(line)?;
+        let payload = CellExtracted {
+            path: path.to_string(),
+            index: count,
+            source,
+        };
+        if let Err(e) = app.emit("cell-extracted", payload) {
+            log::warn!("Failed to emit cell-extracted: {}", e);
+        }
+        count += 1;
+        Ok(())
+    };
+
+    let args = ["--stream", path];
+    let (code, stderr) = drive_sidecar(app, &args, timeout, None, &mut timing, |bytes| {
+        pending.extend(bytes);
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            emit_line(&line)?;
+        }
+        Ok(())
+    })
+    .await?;
+    // Lines are parsed as they arrive, so parsing is part of the read time
+    log::debug!(
+        "Streamed {}: spawn {} ms, read {} ms",
+        path,
+        timing.spawn_ms,
+        timing.read_ms
+    );
+
+    if code != Some(0) {
+        return Err(NbError::sidecar_failed(
+            code,
+            String::from_utf8_lossy(&pending).into_owned(),
+            String::from_utf8_lossy(&stderr).into_owned(),
+        ));
+    }
+    // The last line may lack a trailing newline
+    emit_line(&pending)?;
+    Ok(count)
+}
+
+/// The bundled extractor (or `NBDIFF_EXTRACTOR`) run in `--single` mode,
+/// backed by the managed `InputCache`.
+struct SidecarExtractor {
+    app: tauri::AppHandle,
+}
+
+impl Extractor for SidecarExtractor {
+    fn extract<'a>(
+        &'a self,
+        path: &'a str,
+        timeout: Duration,
+        cancel: Option<&'a CancellationToken>,
+    ) -> BoxFuture<'a, Result<Vec<String>, NbError>> {
+        Box::pin(async move {
+            if !Path::new(path).exists() {
+                return Err(NbError::FileNotFound {
+                    path: path.to_string(),
+                });
+            }
+            let (inputs, _) = timed_sidecar_inputs(&self.app, path, timeout, cancel).await?;
+            Ok(inputs)
+        })
+    }
+
+    fn cache(&self) -> Option<&InputCache> {
+        Some(self.app.state::<InputCache>().inner())
+    }
+}
+
+/// The extractor registered in `run`, borrowed for as long as `app`.
+fn managed_extractor(app: &tauri::AppHandle) -> &dyn Extractor {
+    app.state::<Box<dyn Extractor>>().inner().as_ref()
+}
+
+/// Runs the extractor in `--single` mode and logs how long each stage took.
+async fn timed_sidecar_inputs(
+    app: &tauri::AppHandle,
+    path: &str,
+    timeout: Duration,
+    cancel: Option<&CancellationToken>,
+) -> Result<(Vec<String>, ExtractTiming), NbError> {
+    let started = Instant::now();
+    let (stdout, mut timing) =
+        run_sidecar_timed(app, &["--single", path], Some(timeout), cancel).await?;
+    let parsing = Instant::now();
+    let inputs = parse_sidecar_json(&stdout)?;
+    timing.parse_ms = parsing.elapsed().as_millis() as u64;
+    timing.total_ms = started.elapsed().as_millis() as u64;
+    log::debug!(
+        "Extracted {}: spawn {} ms, read {} ms, parse {} ms, total {} ms",
+        path,
+        timing.spawn_ms,
+        timing.read_ms,
+        timing.parse_ms,
+        timing.total_ms
+    );
+    Ok((inputs, timing))
+}
+
+#[tauri::command]
+async fn extract_inputs(
+    app: tauri::AppHandle,
+    path: String,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<String>, NbError> {
+    let timeout = timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or_else(|| configured_timeout(&app));
+    extractor::extract_inputs(managed_extractor(&app), &path, timeout, None).await
+}
+
+/// Extracts a local file with the sidecar, bypassing the cache, and reports
+/// where the time went.
+#[tauri::command]
+async fn extract_inputs_timed(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<(Vec<String>, ExtractTiming), NbError> {
+    if !Path::new(&path).exists() {
+        return Err(NbError::FileNotFound { path });
+    }
+    timed_sidecar_inputs(&app, &path, configured_timeout(&app), None).await
+}
+
+/// Like `extract_inputs`, but for very large notebooks: each input is emitted
+/// as a `cell-extracted` event as soon as the extractor produces it rather than
+/// collected into one response. Returns the number of cells emitted. Bypasses
+/// the input cache.
+#[tauri::command]
+async fn extract_inputs_streaming(
+    app: tauri::AppHandle,
+    path: String,
+    timeout_ms: Option<u64>,
+) -> Result<usize, NbError> {
+    if !Path::new(&path).exists() {
+        return Err(NbError::FileNotFound { path });
+    }
+    let timeout = timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or_else(|| configured_timeout(&app));
+    stream_sidecar_inputs(&app, &path, Some(timeout)).await
+}
+
+/// The sidecar timeout from the user's settings.
+fn configured_timeout(app: &tauri::AppHandle) -> Duration {
+    Duration::from_millis(settings::load(app).sidecar_timeout_ms)
+}
+
+fn file_name(path: &str) -> &str {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+}
+
+/// Like `extract_inputs`, but keeps each cell's type and execution count.
+/// Jupyter notebooks are read directly; anything else goes through the
+/// sidecar and comes back as code cells.
+#[tauri::command]
+async fn extract_cells(app: tauri::AppHandle, path: String) -> Result<Vec<Cell>, String> {
+    let cells = extractor::extract_cells(managed_extractor(&app), &path, configured_timeout(&app));
+    Ok(cells.await?)
+}
+
+/// Extracts inputs under a caller-chosen `request_id` so that the request can
+/// be aborted with `cancel_extraction`. Starting a new extraction with an id
+/// that is still running cancels the old one.
+#[tauri::command]
+async fn start_extraction(
+    app: tauri::AppHandle,
+    path: String,
+    request_id: String,
+) -> Result<Vec<String>, NbError> {
+    let extractions = app.state::<Extractions>();
+    let (generation, token) = extractions.start(&request_id);
+    let extractor = managed_extractor(&app);
+    let result =
+        extractor::extract_inputs(extractor, &path, configured_timeout(&app), Some(&token)).await;
+    extractions.finish(&request_id, generation);
+    result
+}
+
+#[tauri::command]
+fn cancel_extraction(app: tauri::AppHandle, request_id: String) {
+    app.state::<Extractions>().cancel(&request_id);
+}
+
+async fn run_extract_outputs(
+    app: &tauri::AppHandle,
+    path: &str,
+) -> Result<Vec<CellOutput>, NbError> {
+    let stdout = run_sidecar(
+        app,
+        &["--outputs", path],
+        Some(configured_timeout(app)),
+        None,
+    )
+    .await?;
+    parse_sidecar_json(&stdout)
+}
+
+#[tauri::command]
+async fn extract_outputs(app: tauri::AppHandle, path: String) -> Result<Vec<CellOutput>, String> {
+    Ok(run_extract_outputs(&app, &path).await?)
+}
+
+/// Decodes the PNG and JPEG outputs of a Jupyter notebook to files the
+/// frontend can display.
+#[tauri::command]
+fn extract_output_images(app: tauri::AppHandle, path: String) -> Result<Vec<ImageRef>, String> {
+    let notebook = ipynb::read_notebook(&path)?;
+    Ok(images::extract(&app, &notebook)?)
+}
+
+#[tauri::command]
+fn clear_image_cache(app: tauri::AppHandle) -> Result<(), String> {
+    Ok(images::clear(&app)?)
+}
+
+/// Jupyter notebooks are summarized per cell. The extractor reports a flat
+/// list of outputs, so for other formats each output counts as its own cell.
+async fn load_output_summaries(
+    app: &tauri::AppHandle,
+    path: &str,
+) -> Result<Vec<OutputSummary>, NbError> {
+    if extractor::is_ipynb(path) {
+        let notebook = ipynb::read_notebook(path)?;
+        return Ok(ipynb::cell_values(&notebook)
+            .iter()
+            .map(OutputSummary::from_ipynb_cell)
+            .collect());
+    }
+    let outputs = run_extract_outputs(app, path).await?;
+    Ok(outputs
+        .iter()
+        .map(|output| {
+            OutputSummary::from_sidecar(output.text.as_deref(), output.mime_bundle.as_ref())
+        })
+        .collect())
+}
+
+/// Flags cells that may show stale outputs; see `outputs::stale_output_cells`
+/// for the rules.
+#[tauri::command]
+fn detect_stale_outputs(path: String) -> Result<Vec<usize>, String> {
+    let notebook = ipynb::read_notebook(&path)?;
+    Ok(outputs::stale_output_cells(&notebook))
+}
+
+#[tauri::command]
+async fn diff_outputs(
+    app: tauri::AppHandle,
+    path_a: String,
+    path_b: String,
+) -> Result<Vec<OutputDiff>, String> {
+    let a = load_output_summaries(&app, &path_a).await?;
+    let b = load_output_summaries(&app, &path_b).await?;
+    Ok(outputs::diff_outputs(&a, &b))
+}
+
+async fn compute_diff(
+    app: &tauri::AppHandle,
+    path_a: &str,
+    path_b: &str,
+) -> Result<Vec<CellDiff>, NbError> {
+    let opts = settings::load(app).default_diff_options;
+    let extractor = managed_extractor(app);
+    let timeout = configured_timeout(app);
+    compare::compute_diff_with(extractor, path_a, path_b, &opts, timeout).await
+}
+
+#[tauri::command]
+async fn diff_notebooks(
+    app: tauri::AppHandle,
+    path_a: String,
+    path_b: String,
+) -> Result<Vec<CellDiff>, String> {
+    Ok(compute_diff(&app, &path_a, &path_b).await?)
+}
+
+#[tauri::command]
+async fn diff_notebooks_with(
+    app: tauri::AppHandle,
+    path_a: String,
+    path_b: String,
+    opts: Option<DiffOptions>,
+) -> Result<Vec<CellDiff>, String> {
+    let opts = opts.unwrap_or_else(|| settings::load(&app).default_diff_options);
+    let extractor = managed_extractor(&app);
+    let diffs =
+        compare::compute_diff_with(extractor, &path_a, &path_b, &opts, configured_timeout(&app));
+    Ok(diffs.await?)
+}
+
+/// Diffs the second most recent file (as A) against the most recent (as B),
+/// skipping recents that no longer exist. Does not touch the recents list.
+#[tauri::command]
+async fn diff_recents(app: tauri::AppHandle) -> Result<Vec<CellDiff>, String> {
+    let recents = recents::get(&app)?;
+    let [newest, previous, ..] = recents.as_slice() else {
+        return Err(format!(
+            "Need at least two recent files to diff, found {}",
+            recents.len()
+        ));
+    };
+    Ok(compute_diff(&app, previous, newest).await?)
+}
+
+/// Compares the notebooks under two folders by relative path, under the saved
+/// default diff options; see `compare::diff_folders`.
+#[tauri::command]
+async fn diff_folders(
+    app: tauri::AppHandle,
+    dir_a: String,
+    dir_b: String,
+) -> Result<Vec<FileDiffEntry>, String> {
+    let opts = settings::load(&app).default_diff_options;
+    let extractor = managed_extractor(&app);
+    let entries = compare::diff_folders(extractor, &dir_a, &dir_b, &opts, configured_timeout(&app));
+    Ok(entries.await?)
+}
+
+/// A stable hash of the notebook's cells for "did anything change" checks;
+/// see `diff::content_hash` for what it covers.
+#[tauri::command]
+async fn notebook_content_hash(
+    app: tauri::AppHandle,
+    path: String,
+    opts: DiffOptions,
+) -> Result<String, String> {
+    let extractor = managed_extractor(&app);
+    let cells = extractor::extract_cells(extractor, &path, configured_timeout(&app)).await?;
+    Ok(diff::content_hash(&cells, &opts))
+}
+
+#[tauri::command]
+async fn diff_stats(
+    app: tauri::AppHandle,
+    path_a: String,
+    path_b: String,
+) -> Result<DiffStats, String> {
+    let diffs = compute_diff(&app, &path_a, &path_b).await?;
+    Ok(DiffStats::from_diffs(&diffs))
+}
+
+/// Diffs the committed version of `path` at `rev` (as A) against the working
+/// copy (as B), under the saved default diff options. A file that did not
+/// exist at `rev` diffs as all-added.
+#[tauri::command]
+async fn diff_against_git(
+    app: tauri::AppHandle,
+    path: String,
+    rev: String,
+) -> Result<Vec<CellDiff>, String> {
+    let opts = settings::load(&app).default_diff_options;
+    let extractor = managed_extractor(&app);
+    let timeout = configured_timeout(&app);
+    let current = compare::load_diff_side(extractor, &path, &opts, timeout).await?;
+    let committed = match git::show_at_rev(&path, &rev).await? {
+        Some(contents) => {
+            compare::contents_diff_side(extractor, &contents, &path, &opts, timeout).await?
+        }
+        None => compare::DiffSide {
+            texts: Vec::new(),
+            keys: Vec::new(),
+        },
+    };
+    Ok(diff::diff_cells_keyed(
+        &committed.texts,
+        &current.texts,
+        &committed.keys,
+        &current.keys,
+        &opts,
+    ))
+}
+
+/// Writes the cell diff of two notebooks to `dest` as a self-contained HTML file.
+#[tauri::command]
+async fn export_diff_html(
+    app: tauri::AppHandle,
+    path_a: String,
+    path_b: String,
+    dest: String,
+) -> Result<String, String> {
+    let diffs = compute_diff(&app, &path_a, &path_b).await?;
+    let html = export::diff_to_html(&diffs, file_name(&path_a), file_name(&path_b));
+    std::fs::write(&dest, html).map_err(|e| NbError::Io {
+        path: dest.clone(),
+        detail: e.to_string(),
+    })?;
+    Ok(dest)
+}
+
+/// Returns the cell sources of two notebooks as a unified diff, with
+/// `context` lines of context around each hunk.
+#[tauri::command]
+async fn unified_diff(
+    app: tauri::AppHandle,
+    path_a: String,
+    path_b: String,
+    context: usize,
+) -> Result<String, String> {
+    let extractor = managed_extractor(&app);
+    let timeout = configured_timeout(&app);
+    let a = extractor::extract_sources(extractor, &path_a, timeout).await?;
+    let b = extractor::extract_sources(extractor, &path_b, timeout).await?;
+    Ok(export::unified_diff(&a, &b, &path_a, &path_b, context))
+}
+
+/// Writes resolved merge cells to `dest` as a notebook, taking the top-level
+/// metadata and format version from `template_path`.
+#[tauri::command]
+fn save_merged_notebook(
+    template_path: String,
+    cells: Vec<Cell>,
+    dest: String,
+) -> Result<String, String> {
+    let mut notebook = ipynb::read_notebook(&template_path)?;
+    ipynb::replace_cells(&mut notebook, &cells)?;
+    ipynb::write_notebook(&dest, &notebook)?;
+    Ok(dest)
+}
+
+#[tauri::command]
+async fn search_cells(
+    app: tauri::AppHandle,
+    path: String,
+    query: String,
+    case_sensitive: bool,
+) -> Result<Vec<SearchHit>, String> {
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let extractor = managed_extractor(&app);
+    let sources = extractor::extract_sources(extractor, &path, configured_timeout(&app)).await?;
+    Ok(search::search_cells(&sources, &query, case_sensitive))
+}
+
+/// Exports with `jupyter nbconvert` when it is installed. `format` must be one
+/// of `nbconvert::FORMATS`.
+#[tauri::command]
+async fn export_via_nbconvert(
+    path: String,
+    format: String,
+    dest: String,
+) -> Result<String, String> {
+    nbconvert::export(&path, &format, &dest).await?;
+    Ok(dest)
+}
+
+#[tauri::command]
+async fn merge_notebooks(
+    app: tauri::AppHandle,
+    base: String,
+    ours: String,
+    theirs: String,
+) -> Result<MergeResult, String> {
+    let extractor = managed_extractor(&app);
+    let result = compare::merge_files(extractor, &base, &ours, &theirs, configured_timeout(&app));
+    Ok(result.await?)
 }
 
 #[tauri::command]
@@ -45,25 +763,234 @@ async fn extract_inputs_batch(
     app: tauri::AppHandle,
     path: String,
 ) -> Result<BatchResult, String> {
-    let shell = app.shell();
-    let output = shell
-        .sidecar("extract-inputs")
-        .map_err(|e| format!("Failed to create sidecar command: {}", e))?
-        .args(["--batch", &path])
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run sidecar: {}", e))?;
+    let stdout = run_sidecar(&app, &["--batch", &path], None, None).await?;
+    Ok(parse_sidecar_json(&stdout)?)
+}
+
+/// Extracts several notebooks concurrently, one sidecar per file. A failure on
+/// one file is reported in its entry rather than failing the whole call.
+///
+/// Emits `extract-progress` after each file finishes and `extract-complete`
+/// (with the total file count) exactly once at the end.
+#[tauri::command]
+async fn extract_inputs_many(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+) -> Result<Vec<PathInputs>, String> {
+    let total = paths.len();
+    let limit = settings::load(&app).concurrency_limit();
+    let timeout = configured_timeout(&app);
+    log::info!("Extracting {} files, up to {} at once", total, limit);
+    let mut extractions = stream::iter(paths.into_iter().enumerate())
+        .map(|(i, path)| {
+            let app = app.clone();
+            async move {
+                let extractor = managed_extractor(&app);
+                let result = extractor::extract_inputs(extractor, &path, timeout, None).await;
+                let entry = match result {
+                    Ok(inputs) => PathInputs {
+                        path,
+                        inputs,
+                        error: None,
+                    },
+                    Err(e) => PathInputs {
+                        path,
+                        inputs: Vec::new(),
+                        error: Some(e),
+                    },
+                };
+                (i, entry)
+            }
+        })
+        .buffer_unordered(limit);
+
+    // Events are emitted here, as results are drained one at a time, so the
+    // completed count is always consistent regardless of finishing order.
+    let mut results: Vec<(usize, PathInputs)> = Vec::with_capacity(total);
+    while let Some((i, entry)) = extractions.next().await {
+        let progress = ExtractProgress {
+            completed: results.len() + 1,
+            total,
+            current_path: entry.path.clone(),
+        };
+        if let Err(e) = app.emit("extract-progress", progress) {
+            log::warn!("Failed to emit extract-progress: {}", e);
+        }
+        results.push((i, entry));
+    }
+    if let Err(e) = app.emit("extract-complete", total) {
+        log::warn!("Failed to emit extract-complete: {}", e);
+    }
+
+    results.sort_by_key(|(i, _)| *i);
+    Ok(results.into_iter().map(|(_, entry)| entry).collect())
+}
+
+/// Clears every code cell's outputs and execution count in a `.ipynb` file and
+/// writes the result to `dest`, or back over `path` when no `dest` is given.
+#[tauri::command]
+fn clear_outputs(path: String, dest: Option<String>) -> Result<String, String> {
+    let mut notebook = ipynb::read_notebook(&path)?;
+    ipynb::clear_outputs(&mut notebook);
+    let dest = dest.unwrap_or(path);
+    ipynb::write_notebook(&dest, &notebook)?;
+    Ok(dest)
+}
+
+/// Deletes one cell and writes the notebook to `dest`, or back to `path`.
+#[tauri::command]
+fn remove_cell(path: String, cell_index: usize, dest: Option<String>) -> Result<String, String> {
+    let mut notebook = ipynb::read_notebook(&path)?;
+    ipynb::remove_cell(&mut notebook, cell_index)?;
+    let dest = dest.unwrap_or(path);
+    ipynb::write_notebook(&dest, &notebook)?;
+    Ok(dest)
+}
+
+/// Clears one cell's outputs and writes the notebook to `dest`, or back to
+/// `path`.
+#[tauri::command]
+fn clear_cell_output(
+    path: String,
+    cell_index: usize,
+    dest: Option<String>,
+) -> Result<String, String> {
+    let mut notebook = ipynb::read_notebook(&path)?;
+    ipynb::clear_cell_output(&mut notebook, cell_index)?;
+    let dest = dest.unwrap_or(path);
+    ipynb::write_notebook(&dest, &notebook)?;
+    Ok(dest)
+}
+
+/// Flattens a `.ipynb` file to Markdown, also writing it to `dest` when given.
+#[tauri::command]
+fn notebook_to_markdown(
+    path: String,
+    dest: Option<String>,
+    include_outputs: bool,
+) -> Result<String, String> {
+    let notebook = ipynb::read_notebook(&path)?;
+    let markdown = export::notebook_to_markdown(&notebook, include_outputs)?;
+    if let Some(dest) = dest {
+        std::fs::write(&dest, &markdown).map_err(|e| NbError::Io {
+            path: dest.clone(),
+            detail: e.to_string(),
+        })?;
+    }
+    Ok(markdown)
+}
+
+#[tauri::command]
+fn compare_formats(path_a: String, path_b: String) -> Result<FormatComparison, String> {
+    let notebook_a = ipynb::read_notebook(&path_a)?;
+    let notebook_b = ipynb::read_notebook(&path_b)?;
+    Ok(ipynb::compare_formats(&notebook_a, &notebook_b)?)
+}
+
+/// Returns the canonical JSON of a `.ipynb` file (see `ipynb::normalize`)
+/// without touching the file itself.
+#[tauri::command]
+fn normalize_notebook(path: String) -> Result<String, String> {
+    let mut notebook = ipynb::read_notebook(&path)?;
+    ipynb::normalize(&mut notebook)?;
+    Ok(ipynb::to_json(&notebook)?)
+}
+
+#[tauri::command]
+fn extract_metadata(path: String) -> Result<NotebookMeta, String> {
+    let notebook = ipynb::read_notebook(&path)?;
+    Ok(ipynb::metadata(&notebook)?)
+}
+
+#[tauri::command]
+fn validate_notebook(path: String) -> Result<ValidationReport, String> {
+    if !Path::new(&path).exists() {
+        return Err(NbError::FileNotFound { path }.into());
+    }
+    let contents = std::fs::read(&path).map_err(|e| NbError::Io {
+        path: path.clone(),
+        detail: e.to_string(),
+    })?;
+    Ok(ipynb::validate(&contents))
+}
+
+#[tauri::command]
+fn add_recent(app: tauri::AppHandle, path: String) -> Result<(), String> {
+    Ok(recents::add(&app, &path)?)
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("extract-inputs batch failed: {}", stderr));
+/// Opens a native picker for one or more notebooks, starting in the folder of
+/// the most recently opened file. Cancelling returns an empty list.
+#[tauri::command]
+async fn pick_notebooks(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    let mut dialog = app
+        .dialog()
+        .file()
+        .set_title("Open notebooks")
+        .add_filter("Notebooks", &["ipynb", "py"]);
+    let recent_dir = recents::get(&app)
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|path| Path::new(&path).parent().map(Path::to_path_buf));
+    if let Some(dir) = recent_dir {
+        dialog = dialog.set_directory(dir);
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let result: BatchResult =
-        serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse batch JSON: {}", e))?;
+    // The blocking picker waits on the native dialog, so keep it off the
+    // async worker threads
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_pick_files())
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(picked
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| path.into_path().ok())
+        .map(|path| path.display().to_string())
+        .collect())
+}
 
-    Ok(result)
+#[tauri::command]
+fn get_recents(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    Ok(recents::get(&app)?)
+}
+
+/// Emits `notebook-changed` (with the path) whenever the file is modified.
+#[tauri::command]
+fn watch_notebook(app: tauri::AppHandle, path: String) -> Result<(), String> {
+    Ok(watch::watch(&app, &path)?)
+}
+
+#[tauri::command]
+fn unwatch_notebook(app: tauri::AppHandle, path: String) -> Result<(), String> {
+    watch::unwatch(&app, &path);
+    Ok(())
+}
+
+#[tauri::command]
+fn load_settings(app: tauri::AppHandle) -> Settings {
+    settings::load(&app)
+}
+
+#[tauri::command]
+fn save_settings(app: tauri::AppHandle, settings: Settings) -> Result<(), String> {
+    Ok(settings::save(&app, &settings)?)
+}
+
+/// Path of the current log file, for attaching to bug reports.
+#[tauri::command]
+fn get_log_path(app: tauri::AppHandle) -> Result<String, String> {
+    let dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to resolve log directory: {}", e))?;
+    let path = dir.join(LOG_FILE_NAME).with_extension("log");
+    Ok(path.display().to_string())
+}
+
+/// Drops all cached extraction results.
+#[tauri::command]
+fn clear_cache(app: tauri::AppHandle) {
+    app.state::<InputCache>().clear();
 }
 
 #[tauri::command]
@@ -77,21 +1004,83 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .manage(watch::Watchers::default())
+        .manage(InputCache::default())
+        .manage(Extractions::default())
         .setup(|app| {
+            let extractor: Box<dyn Extractor> = Box::new(SidecarExtractor {
+                app: app.handle().clone(),
+            });
+            app.manage(extractor);
+
+            let mut logger = tauri_plugin_log::Builder::default()
+                .clear_targets()
+                .target(Target::new(TargetKind::LogDir {
+                    file_name: Some(LOG_FILE_NAME.to_string()),
+                }))
+                .max_file_size(LOG_MAX_FILE_SIZE)
+                .rotation_strategy(RotationStrategy::KeepSome(LOG_KEEP_FILES))
+                .level(log::LevelFilter::Info);
             if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
+                // Our debug logs carry the per-extraction timings
+                logger = logger
+                    .target(Target::new(TargetKind::Stdout))
+                    .level_for(module_path!(), log::LevelFilter::Debug);
             }
+            app.handle().plugin(logger.build())?;
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             extract_inputs,
+            extract_inputs_streaming,
+            extract_inputs_timed,
+            start_extraction,
+            cancel_extraction,
             extract_inputs_batch,
+            extract_inputs_many,
+            extract_cells,
+            extract_outputs,
+            extract_output_images,
+            clear_image_cache,
+            diff_outputs,
+            detect_stale_outputs,
+            diff_notebooks,
+            diff_notebooks_with,
+            diff_stats,
+            diff_folders,
+            notebook_content_hash,
+            diff_recents,
+            diff_against_git,
+            export_diff_html,
+            unified_diff,
+            export_via_nbconvert,
+            merge_notebooks,
+            save_merged_notebook,
+            search_cells,
+            clear_outputs,
+            remove_cell,
+            clear_cell_output,
+            notebook_to_markdown,
+            normalize_notebook,
+            extract_metadata,
+            compare_formats,
+            validate_notebook,
+            add_recent,
+            get_recents,
+            pick_notebooks,
+            watch_notebook,
+            unwatch_notebook,
+            load_settings,
+            save_settings,
+            get_log_path,
+            clear_cache,
             is_directory
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                watch::unwatch_all(app);
+            }
+        });
 }
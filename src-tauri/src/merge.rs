@@ -0,0 +1,129 @@
+use crate::diff::{myers, Edit};
+
+/// Which side a merged cell was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeOrigin {
+    /// Unchanged on both sides.
+    Base,
+    Ours,
+    Theirs,
+    /// Both sides made the identical change.
+    Both,
+}
+
+/// One cell of the merged notebook, or a placeholder for an unresolved
+/// conflict. `conflict` indexes into `MergeResult::conflicts`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum MergedCell {
+    Resolved { text: String, origin: MergeOrigin },
+    Conflict { conflict: usize },
+}
+
+/// A run of cells that both sides changed differently. Each side's version of
+/// the region is kept in full so the frontend can offer either.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ConflictRegion {
+    pub base: Vec<String>,
+    pub ours: Vec<String>,
+    pub theirs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct MergeResult {
+    pub cells: Vec<MergedCell>,
+    pub conflicts: Vec<ConflictRegion>,
+}
+
+/// For each cell in `base`, the index of the matching cell in `other`, if any.
+fn base_matches(base: &[String], other: &[String]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+    for edit in myers(base, other) {
+        if let Edit::Equal(i, j) = edit {
+            matches[i] = Some(j);
+        }
+    }
+    matches
+}
+
+/// Cell-level three-way merge in the style of diff3.
+///
+/// Base cells that survive unchanged on both sides act as sync points. Between
+/// sync points, a region changed on only one side takes that side's version,
+/// a region changed identically on both sides is taken once, and anything else
+/// is reported as a conflict.
+pub fn merge_cells(base: &[String], ours: &[String], theirs: &[String]) -> MergeResult {
+    let ours_match = base_matches(base, ours);
+    let theirs_match = base_matches(base, theirs);
+
+    let mut result = MergeResult {
+        cells: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    let (mut o, mut a, mut b) = (0, 0, 0);
+
+    loop {
+        if o < base.len() && ours_match[o] == Some(a) && theirs_match[o] == Some(b) {
+            result.cells.push(MergedCell::Resolved {
+                text: base[o].clone(),
+                origin: MergeOrigin::Base,
+            });
+            o += 1;
+            a += 1;
+            b += 1;
+            continue;
+        }
+
+        let next_sync = (o..base.len()).find_map(|i| match (ours_match[i], theirs_match[i]) {
+            (Some(j), Some(k)) => Some((i, j, k)),
+            _ => None,
+        });
+        let (o_end, a_end, b_end) = next_sync.unwrap_or((base.len(), ours.len(), theirs.len()));
+
+        let base_chunk = &base[o..o_end];
+        let ours_chunk = &ours[a..a_end];
+        let theirs_chunk = &theirs[b..b_end];
+
+        let taken = if ours_chunk == base_chunk {
+            Some((theirs_chunk, MergeOrigin::Theirs))
+        } else if theirs_chunk == base_chunk {
+            Some((ours_chunk, MergeOrigin::Ours))
+        } else if ours_chunk == theirs_chunk {
+            Some((ours_chunk, MergeOrigin::Both))
+        } else {
+            None
+        };
+        match taken {
+            Some((cells, origin)) => {
+                result
+                    .cells
+                    .extend(cells.iter().map(|text| MergedCell::Resolved {
+                        text: text.clone(),
+                        origin,
+                    }));
+            }
+            None => {
+                result.cells.push(MergedCell::Conflict {
+                    conflict: result.conflicts.len(),
+                });
+                result.conflicts.push(ConflictRegion {
+                    base: base_chunk.to_vec(),
+                    ours: ours_chunk.to_vec(),
+                    theirs: theirs_chunk.to_vec(),
+                });
+            }
+        }
+
+        if next_sync.is_none() {
+            break;
+        }
+        (o, a, b) = (o_end, a_end, b_end);
+    }
+
+    result
+}
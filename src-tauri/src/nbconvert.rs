@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use crate::error::NbError;
+
+/// The `--to` targets `export_via_nbconvert` accepts.
+pub const FORMATS: &[&str] = &["html", "markdown", "pdf", "script"];
+
+fn nbconvert_err(detail: String, stderr: Option<String>) -> NbError {
+    NbError::Nbconvert { detail, stderr }
+}
+
+/// Runs `jupyter nbconvert --to <format>` on `path`, writing to `dest`.
+/// nbconvert appends the format's extension when `dest` lacks it, so `dest`
+/// should normally include it already.
+pub async fn export(path: &str, format: &str, dest: &str) -> Result<(), NbError> {
+    if !FORMATS.contains(&format) {
+        return Err(nbconvert_err(
+            format!(
+                "unsupported format {:?}, expected one of: {}",
+                format,
+                FORMATS.join(", ")
+            ),
+            None,
+        ));
+    }
+    if !Path::new(path).exists() {
+        return Err(NbError::FileNotFound {
+            path: path.to_string(),
+        });
+    }
+
+    // nbconvert resolves `--output` relative to `--output-dir`, which
+    // defaults to the notebook's directory rather than the working directory
+    let dest = Path::new(dest);
+    let (Some(name), Some(dir)) = (dest.file_name(), dest.parent()) else {
+        return Err(nbconvert_err(
+            format!("invalid destination: {}", dest.display()),
+            None,
+        ));
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+
+    let output = tokio::process::Command::new("jupyter")
+        .args(["nbconvert", "--to", format])
+        .arg(path)
+        .arg("--output-dir")
+        .arg(dir)
+        .arg("--output")
+        .arg(name)
+        .output()
+        .await
+        .map_err(|e| {
+            let detail = if e.kind() == std::io::ErrorKind::NotFound {
+                "`jupyter` was not found on PATH; install nbconvert or use the \
+                 built-in HTML and Markdown exporters instead"
+                    .to_string()
+            } else {
+                format!("could not run jupyter nbconvert: {}", e)
+            };
+            nbconvert_err(detail, None)
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(nbconvert_err(
+            match output.status.code() {
+                Some(code) => format!("nbconvert exited with code {}", code),
+                None => "nbconvert was terminated".to_string(),
+            },
+            Some(stderr),
+        ));
+    }
+    Ok(())
+}
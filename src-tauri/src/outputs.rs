@@ -0,0 +1,141 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use serde_json::Value;
+
+use crate::ipynb;
+
+/// How a cell's outputs differ between two notebooks. `Added` and `Removed`
+/// cover cells that only exist on one side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputChange {
+    Unchanged,
+    TextChanged,
+    ImageChanged,
+    Added,
+    Removed,
+}
+
+/// The comparison of one cell's outputs. A rerun that only bumped the
+/// execution count shows up as `Unchanged` with `execution_count_changed` set.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputDiff {
+    pub cell_index: usize,
+    pub change: OutputChange,
+    pub execution_count_changed: bool,
+}
+
+/// One cell's outputs reduced to what `diff_outputs` compares. Images are kept
+/// only as a hash of their base64 payload.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputSummary {
+    texts: Vec<String>,
+    image_hashes: Vec<u64>,
+    execution_count: Option<u64>,
+}
+
+fn hash_payload(payload: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    payload.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn is_image(mime: &str) -> bool {
+    mime.starts_with("image/")
+}
+
+impl OutputSummary {
+    /// Summarizes a cell from a Jupyter notebook's JSON.
+    pub fn from_ipynb_cell(cell: &Value) -> Self {
+        OutputSummary {
+            texts: ipynb::output_texts(cell),
+            image_hashes: ipynb::output_images(cell)
+                .iter()
+                .map(|(_, payload)| hash_payload(payload))
+                .collect(),
+            execution_count: cell.get("execution_count").and_then(Value::as_u64),
+        }
+    }
+
+    /// Summarizes one output reported by the extractor, which has no execution
+    /// counts and no grouping of outputs into cells.
+    pub fn from_sidecar(text: Option<&str>, mime_bundle: Option<&HashMap<String, String>>) -> Self {
+        let mut images: Vec<(&String, &String)> = mime_bundle
+            .into_iter()
+            .flatten()
+            .filter(|(mime, _)| is_image(mime))
+            .collect();
+        images.sort();
+        OutputSummary {
+            texts: text.map(str::to_string).into_iter().collect(),
+            image_hashes: images
+                .into_iter()
+                .map(|(_, payload)| hash_payload(payload))
+                .collect(),
+            execution_count: None,
+        }
+    }
+}
+
+/// Pairs cells by index and classifies how their outputs changed. A change to
+/// any image takes precedence over text changes in the same cell.
+pub fn diff_outputs(a: &[OutputSummary], b: &[OutputSummary]) -> Vec<OutputDiff> {
+    (0..a.len().max(b.len()))
+        .map(|cell_index| {
+            let (change, execution_count_changed) = match (a.get(cell_index), b.get(cell_index)) {
+                (Some(a), Some(b)) => {
+                    let change = if a.image_hashes != b.image_hashes {
+                        OutputChange::ImageChanged
+                    } else if a.texts != b.texts {
+                        OutputChange::TextChanged
+                    } else {
+                        OutputChange::Unchanged
+                    };
+                    (change, a.execution_count != b.execution_count)
+                }
+                (Some(_), None) => (OutputChange::Removed, false),
+                (None, _) => (OutputChange::Added, false),
+            };
+            OutputDiff {
+                cell_index,
+                change,
+                execution_count_changed,
+            }
+        })
+        .collect()
+}
+
+/// Indices of code cells whose outputs may not match their source. A cell is
+/// flagged when it has at least one output and either
+///
+/// - its `execution_count` is null, so the outputs cannot be tied to a
+///   recorded run of the current source, or
+/// - its `execution_count` is lower than that of some earlier code cell, so
+///   the notebook was run out of order and earlier cells may have changed
+///   the state its outputs were produced from.
+///
+/// Cells without outputs are never flagged, and markdown and raw cells are
+/// skipped entirely. This is a heuristic: it can't see edits made after a run.
+pub fn stale_output_cells(notebook: &Value) -> Vec<usize> {
+    let mut stale = Vec::new();
+    let mut highest: Option<u64> = None;
+    for (i, cell) in ipynb::cell_values(notebook).iter().enumerate() {
+        if cell.get("cell_type").and_then(Value::as_str) != Some("code") {
+            continue;
+        }
+        let has_outputs = cell
+            .get("outputs")
+            .and_then(Value::as_array)
+            .is_some_and(|outputs| !outputs.is_empty());
+        let count = cell.get("execution_count").and_then(Value::as_u64);
+        let out_of_order = matches!((count, highest), (Some(n), Some(max)) if n < max);
+        if has_outputs && (count.is_none() || out_of_order) {
+            stale.push(i);
+        }
+        highest = highest.max(count);
+    }
+    stale
+}
@@ -0,0 +1,87 @@
+use crate::cell::{Cell, CellType};
+
+const MARKER: &str = "# %%";
+
+/// The type a `# %%` marker line declares, or `None` if `line` is not a marker.
+/// `# %%` alone (optionally with a title) is code; `[markdown]`/`[md]` and
+/// `[raw]` select the other types.
+fn marker_type(line: &str) -> Option<CellType> {
+    let rest = line.strip_prefix(MARKER)?;
+    if !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+        return None;
+    }
+    if rest.contains("[markdown]") || rest.contains("[md]") {
+        Some(CellType::Markdown)
+    } else if rest.contains("[raw]") {
+        Some(CellType::Raw)
+    } else {
+        Some(CellType::Code)
+    }
+}
+
+/// Strips the comment prefix jupytext adds to every markdown line.
+fn uncomment(line: &str) -> &str {
+    if line == "#" {
+        ""
+    } else {
+        line.strip_prefix("# ").unwrap_or(line)
+    }
+}
+
+/// Number of leading lines taken up by a jupytext `# ---` ... `# ---` YAML
+/// header, or 0 if the script has none.
+fn header_len(lines: &[&str]) -> usize {
+    if lines.first().map(|l| l.trim_end()) != Some("# ---") {
+        return 0;
+    }
+    lines[1..]
+        .iter()
+        .position(|l| l.trim_end() == "# ---")
+        .map_or(0, |end| end + 2)
+}
+
+fn push_cell(cells: &mut Vec<Cell>, cell_type: CellType, lines: &[&str]) {
+    let body: Vec<&str> = match cell_type {
+        CellType::Markdown | CellType::Raw => lines.iter().map(|l| uncomment(l)).collect(),
+        CellType::Code => lines.to_vec(),
+    };
+    // Blank lines around a cell only separate it from its neighbours
+    let source = body.join("\n").trim_matches('\n').to_string();
+    cells.push(Cell::new(cell_type, source, None, Vec::new()));
+}
+
+/// Splits a jupytext percent-format script into cells. Any code before the first
+/// marker (after the optional jupytext header) becomes a leading code cell.
+pub fn parse(text: &str) -> Vec<Cell> {
+    let lines: Vec<&str> = text.lines().collect();
+    let lines = &lines[header_len(&lines)..];
+
+    let mut cells = Vec::new();
+    let mut current: Option<CellType> = None;
+    let mut start = 0;
+
+    for (i, line) in lines.iter().enumerate() {
+        let Some(next_type) = marker_type(line) else {
+            continue;
+        };
+        match current {
+            Some(cell_type) => push_cell(&mut cells, cell_type, &lines[start..i]),
+            None if lines[..i].iter().any(|l| !l.trim().is_empty()) => {
+                push_cell(&mut cells, CellType::Code, &lines[..i]);
+            }
+            None => {}
+        }
+        current = Some(next_type);
+        start = i + 1;
+    }
+
+    match current {
+        Some(cell_type) => push_cell(&mut cells, cell_type, &lines[start..]),
+        None if lines.iter().any(|l| !l.trim().is_empty()) => {
+            push_cell(&mut cells, CellType::Code, lines);
+        }
+        None => {}
+    }
+
+    cells
+}
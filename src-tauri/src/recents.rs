@@ -0,0 +1,64 @@
+use std::path::{Path, PathBuf};
+
+use tauri::Manager;
+
+use crate::error::NbError;
+
+/// How many paths are kept in the recent-files list.
+const MAX_RECENTS: usize = 20;
+
+const RECENTS_FILE: &str = "recents.json";
+
+fn recents_file(app: &tauri::AppHandle) -> Result<PathBuf, NbError> {
+    let dir = app.path().app_config_dir().map_err(|e| NbError::Io {
+        path: "app config dir".to_string(),
+        detail: e.to_string(),
+    })?;
+    Ok(dir.join(RECENTS_FILE))
+}
+
+/// Reads the stored list as-is. A missing or corrupt file is treated as empty.
+fn read_stored(file: &Path) -> Vec<String> {
+    std::fs::read_to_string(file)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn write_stored(file: &Path, recents: &[String]) -> Result<(), NbError> {
+    let io_err = |e: std::io::Error| NbError::Io {
+        path: file.display().to_string(),
+        detail: e.to_string(),
+    };
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir).map_err(io_err)?;
+    }
+    let json = serde_json::to_string_pretty(recents).map_err(|e| NbError::Parse {
+        detail: e.to_string(),
+    })?;
+    std::fs::write(file, json).map_err(io_err)
+}
+
+/// Moves `path` to the front of `recents`, dropping any earlier occurrence and
+/// trimming the list to `MAX_RECENTS`.
+fn push_front(recents: &mut Vec<String>, path: &str) {
+    recents.retain(|p| p != path);
+    recents.insert(0, path.to_string());
+    recents.truncate(MAX_RECENTS);
+}
+
+pub fn add(app: &tauri::AppHandle, path: &str) -> Result<(), NbError> {
+    let file = recents_file(app)?;
+    let mut recents = read_stored(&file);
+    push_front(&mut recents, path);
+    write_stored(&file, &recents)
+}
+
+/// Returns recent paths, most recent first, skipping files that no longer exist.
+pub fn get(app: &tauri::AppHandle) -> Result<Vec<String>, NbError> {
+    let file = recents_file(app)?;
+    Ok(read_stored(&file)
+        .into_iter()
+        .filter(|p| Path::new(p).exists())
+        .collect())
+}
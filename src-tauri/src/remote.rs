@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use crate::error::NbError;
+
+pub fn is_remote(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// The last path segment of `url`, used to give the downloaded copy the same
+/// file name (and so the same extension) as the remote notebook.
+pub fn file_name(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| {
+            u.path_segments()
+                .and_then(|mut segments| segments.next_back().map(str::to_string))
+        })
+        .unwrap_or_default()
+}
+
+/// Downloads `url`, failing on anything other than a 200 response.
+pub async fn fetch(url: &str, timeout: Duration) -> Result<Vec<u8>, NbError> {
+    let http_err = |status: Option<u16>, detail: String| NbError::Http {
+        url: url.to_string(),
+        status,
+        detail,
+    };
+    let request_err = |e: reqwest::Error| {
+        if e.is_timeout() {
+            http_err(
+                None,
+                format!("request timed out after {} ms", timeout.as_millis()),
+            )
+        } else {
+            http_err(e.status().map(|s| s.as_u16()), e.to_string())
+        }
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(request_err)?;
+    let response = client.get(url).send().await.map_err(request_err)?;
+
+    let status = response.status();
+    if status != reqwest::StatusCode::OK {
+        return Err(http_err(
+            Some(status.as_u16()),
+            format!("server responded with {}", status),
+        ));
+    }
+
+    let body = response.bytes().await.map_err(request_err)?;
+    Ok(body.to_vec())
+}
@@ -0,0 +1,41 @@
+/// A line of a cell that contains the search query. `cell_index` and `line`
+/// are zero-based; `snippet` is the whole line.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub cell_index: usize,
+    pub line: usize,
+    pub snippet: String,
+}
+
+/// Plain substring search over cell sources, reporting every line that
+/// contains `query`. An empty query matches nothing.
+pub fn search_cells(sources: &[String], query: &str, case_sensitive: bool) -> Vec<SearchHit> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let needle = if case_sensitive {
+        query.to_string()
+    } else {
+        query.to_lowercase()
+    };
+
+    let mut hits = Vec::new();
+    for (cell_index, source) in sources.iter().enumerate() {
+        for (line, text) in source.lines().enumerate() {
+            let found = if case_sensitive {
+                text.contains(&needle)
+            } else {
+                text.to_lowercase().contains(&needle)
+            };
+            if found {
+                hits.push(SearchHit {
+                    cell_index,
+                    line,
+                    snippet: text.to_string(),
+                });
+            }
+        }
+    }
+    hits
+}
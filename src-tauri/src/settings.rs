@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+use tauri::Manager;
+
+use crate::diff::DiffOptions;
+use crate::error::NbError;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+pub const DEFAULT_SIDECAR_TIMEOUT_MS: u64 = 30_000;
+
+/// Default cap on concurrent extractions, also when the CPU count is unknown.
+const DEFAULT_MAX_CONCURRENT_EXTRACTIONS: usize = 8;
+
+/// Range a saved `max_concurrent_extractions` is clamped to.
+const CONCURRENT_EXTRACTIONS_RANGE: std::ops::RangeInclusive<usize> = 1..=32;
+
+/// User preferences persisted across launches. Fields missing from the file
+/// take their default, so older settings files keep working.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    /// `"light"`, `"dark"` or `"system"`.
+    pub theme: String,
+    /// Used by the diff commands when no options are passed.
+    pub default_diff_options: DiffOptions,
+    /// Used by extraction commands when no timeout is passed.
+    pub sidecar_timeout_ms: u64,
+    /// Sidecars run at once by `extract_inputs_many`. Read it through
+    /// `concurrency_limit`, which clamps it.
+    pub max_concurrent_extractions: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            theme: "system".to_string(),
+            default_diff_options: DiffOptions::default(),
+            sidecar_timeout_ms: DEFAULT_SIDECAR_TIMEOUT_MS,
+            max_concurrent_extractions: std::thread::available_parallelism()
+                .map_or(DEFAULT_MAX_CONCURRENT_EXTRACTIONS, |cpus| {
+                    cpus.get().min(DEFAULT_MAX_CONCURRENT_EXTRACTIONS)
+                }),
+        }
+    }
+}
+
+impl Settings {
+    /// `max_concurrent_extractions` clamped to a range that neither stalls
+    /// batches nor floods the machine with sidecars.
+    pub fn concurrency_limit(&self) -> usize {
+        self.max_concurrent_extractions.clamp(
+            *CONCURRENT_EXTRACTIONS_RANGE.start(),
+            *CONCURRENT_EXTRACTIONS_RANGE.end(),
+        )
+    }
+}
+
+fn settings_file(app: &tauri::AppHandle) -> Result<PathBuf, NbError> {
+    let dir = app.path().app_config_dir().map_err(|e| NbError::Io {
+        path: "app config dir".to_string(),
+        detail: e.to_string(),
+    })?;
+    Ok(dir.join(SETTINGS_FILE))
+}
+
+/// Reads the saved settings. A missing or corrupt file gives the defaults.
+pub fn load(app: &tauri::AppHandle) -> Settings {
+    let file = match settings_file(app) {
+        Ok(file) => file,
+        Err(e) => {
+            log::warn!("Using default settings: {}", e);
+            return Settings::default();
+        }
+    };
+    let Ok(contents) = std::fs::read_to_string(&file) else {
+        return Settings::default();
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        log::warn!("Ignoring corrupt {}: {}", file.display(), e);
+        Settings::default()
+    })
+}
+
+pub fn save(app: &tauri::AppHandle, settings: &Settings) -> Result<(), NbError> {
+    let file = settings_file(app)?;
+    let io_err = |e: std::io::Error| NbError::Io {
+        path: file.display().to_string(),
+        detail: e.to_string(),
+    };
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir).map_err(io_err)?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|e| NbError::Parse {
+        detail: e.to_string(),
+    })?;
+    std::fs::write(&file, json).map_err(io_err)
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use tauri::{Emitter, Manager};
+
+use crate::error::NbError;
+
+/// Editors often write a file several times per save; events closer together
+/// than this are collapsed into a single `notebook-changed` event.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Active watchers keyed by the path passed to `watch_notebook`. Dropping a
+/// debouncer stops its watcher.
+#[derive(Default)]
+pub struct Watchers(Mutex<HashMap<String, Debouncer<RecommendedWatcher>>>);
+
+fn io_err(path: &str, detail: impl ToString) -> NbError {
+    NbError::Io {
+        path: path.to_string(),
+        detail: detail.to_string(),
+    }
+}
+
+/// Starts watching `path`, replacing any existing watcher for it. The parent
+/// directory is watched rather than the file itself so that editors which
+/// save by writing a temp file and renaming it over the original are caught.
+pub fn watch(app: &tauri::AppHandle, path: &str) -> Result<(), NbError> {
+    let target: PathBuf = Path::new(path)
+        .canonicalize()
+        .map_err(|_| NbError::FileNotFound {
+            path: path.to_string(),
+        })?;
+    let dir = target
+        .parent()
+        .ok_or_else(|| io_err(path, "file has no parent directory"))?
+        .to_path_buf();
+
+    let handle = app.clone();
+    let watched = path.to_string();
+    let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| match result {
+        Ok(events) if events.iter().any(|e| e.path == target) => {
+            if let Err(e) = handle.emit("notebook-changed", &watched) {
+                log::warn!("Failed to emit notebook-changed: {}", e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Watcher error for {}: {}", watched, e),
+    })
+    .map_err(|e| io_err(path, e))?;
+
+    debouncer
+        .watcher()
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| io_err(path, e))?;
+
+    let watchers = app.state::<Watchers>();
+    watchers
+        .0
+        .lock()
+        .unwrap()
+        .insert(path.to_string(), debouncer);
+    Ok(())
+}
+
+pub fn unwatch(app: &tauri::AppHandle, path: &str) {
+    app.state::<Watchers>().0.lock().unwrap().remove(path);
+}
+
+pub fn unwatch_all(app: &tauri::AppHandle) {
+    app.state::<Watchers>().0.lock().unwrap().clear();
+}
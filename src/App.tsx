@@ -23,10 +23,23 @@ interface BatchResult {
   failed: number;
 }
 
+/** Mirrors the serialized `NbError` from the Rust backend. */
+interface NbError {
+  kind: string;
+  message: string;
+}
+
 function formatInputs(inputs: string[]): string {
   return inputs.map((input, i) => `(* Input ${i + 1} *)\n${input}`).join("\n\n");
 }
 
+function errorMessage(e: unknown): string {
+  if (typeof e === "object" && e !== null && "message" in e) {
+    return (e as NbError).message;
+  }
+  return String(e);
+}
+
 function App() {
   const [leftContent, setLeftContent] = useState<string | null>(null);
   const [rightContent, setRightContent] = useState<string | null>(null);
@@ -116,8 +129,9 @@ function App() {
         });
         setContent(formatInputs(result));
         setFile(filePath.split("/").pop() || filePath);
+        invoke("add_recent", { path: filePath }).catch(() => {});
       } catch (e) {
-        setError(`Error: ${e}`);
+        setError(`Error: ${errorMessage(e)}`);
         setContent(null);
         setFile(null);
       } finally {
@@ -158,7 +172,7 @@ function App() {
       setRightContent(submissions[0].content);
       setRightFile(submissions[0].relativePath);
     } catch (e) {
-      setError(`Batch extraction failed: ${e}`);
+      setError(`Batch extraction failed: ${errorMessage(e)}`);
     } finally {
       setRightLoading(false);
     }